@@ -0,0 +1,163 @@
+//! Headless galaxy generation for balancing and design reviews.
+//!
+//! ```text
+//! junk-mapgen export <seed> [--shape square] [--size small] [--out .]
+//! junk-mapgen stats <seed> [--shape square] [--size small]
+//! junk-mapgen check <first seed> <count> [--shape square] [--size small]
+//!     [--min-systems 30] [--min-reachable 0.9] [--min-reach 6]
+//!     [--min-start-degree 2] [--max-start-degree 8] [--max-components 3]
+//!     [--rerolls 20]
+//! ```
+
+use std::{fs, path::PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use junk_unav::{GalaxyShape, GalaxySize, GalaxyThresholds, UNav};
+
+const USAGE: &str = "usage: junk-mapgen <export|stats|check> ..., see the crate docs";
+
+/// Options shared by every command.
+struct MapOptions {
+    shape: GalaxyShape,
+    size: GalaxySize,
+    out: PathBuf,
+    thresholds: GalaxyThresholds,
+    /// How many times `check` rerolls a rejected seed.
+    rerolls: usize,
+}
+
+impl Default for MapOptions {
+    fn default() -> Self {
+        Self {
+            shape: GalaxyShape::default(),
+            size: GalaxySize::default(),
+            out: PathBuf::from("."),
+            thresholds: GalaxyThresholds::default(),
+            rerolls: 20,
+        }
+    }
+}
+
+/// Splits `--flag value` options from positional arguments.
+fn parse_options(args: &[String]) -> Result<(MapOptions, Vec<String>)> {
+    let mut options = MapOptions::default();
+    let mut positional = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| anyhow!("{} needs a value", arg));
+        match arg.as_str() {
+            "--shape" => {
+                let name = value()?;
+                options.shape = GalaxyShape::from_name(name)
+                    .ok_or_else(|| anyhow!("unknown shape {}", name))?;
+            }
+            "--size" => {
+                let name = value()?;
+                options.size =
+                    GalaxySize::from_name(name).ok_or_else(|| anyhow!("unknown size {}", name))?;
+            }
+            "--out" => options.out = PathBuf::from(value()?),
+            "--min-systems" => options.thresholds.min_systems = value()?.parse()?,
+            "--min-reachable" => options.thresholds.min_reachable = value()?.parse()?,
+            "--min-reach" => options.thresholds.min_reach = value()?.parse()?,
+            "--min-start-degree" => options.thresholds.min_start_degree = value()?.parse()?,
+            "--max-start-degree" => options.thresholds.max_start_degree = value()?.parse()?,
+            "--max-components" => options.thresholds.max_components = value()?.parse()?,
+            "--rerolls" => options.rerolls = value()?.parse()?,
+            _ if arg.starts_with("--") => bail!("unknown option {}", arg),
+            _ => positional.push(arg.clone()),
+        }
+    }
+    Ok((options, positional))
+}
+
+fn export(args: &[String]) -> Result<()> {
+    let (options, positional) = parse_options(args)?;
+    let [seed] = positional.as_slice() else {
+        bail!(USAGE);
+    };
+    let seed: u32 = seed.parse().context("seed must be a number")?;
+
+    let unav = UNav::generate_shaped(seed, options.shape, options.size);
+    fs::create_dir_all(&options.out)?;
+    let stem = format!(
+        "unav-{}-{}-{}",
+        seed,
+        options.shape.name(),
+        options.size.name()
+    );
+    let write = |extension: &str, contents: String| -> Result<()> {
+        let path = options.out.join(format!("{}.{}", stem, extension));
+        fs::write(&path, contents).with_context(|| format!("writing {}", path.display()))?;
+        println!("wrote {}", path.display());
+        Ok(())
+    };
+    write("dot", unav.to_dot())?;
+    write("json", unav.to_json()?)?;
+    write("svg", unav.to_svg())?;
+    Ok(())
+}
+
+fn stats(args: &[String]) -> Result<()> {
+    let (options, positional) = parse_options(args)?;
+    let [seed] = positional.as_slice() else {
+        bail!(USAGE);
+    };
+    let seed: u32 = seed.parse().context("seed must be a number")?;
+
+    let stats = UNav::generate_shaped(seed, options.shape, options.size).stats();
+    println!("{}", stats);
+    for failure in options.thresholds.failures(&stats) {
+        println!("Rejected: {}", failure);
+    }
+    Ok(())
+}
+
+/// Runs the thresholds over a range of seeds, printing the rejected ones and
+/// what they were rerolled to.
+fn check(args: &[String]) -> Result<()> {
+    let (options, positional) = parse_options(args)?;
+    let [first, count] = positional.as_slice() else {
+        bail!(USAGE);
+    };
+    let first: u32 = first.parse().context("first seed must be a number")?;
+    let count: u32 = count.parse().context("count must be a number")?;
+
+    let mut rejected = 0;
+    let mut unrecoverable = 0;
+    for seed in first..first.saturating_add(count) {
+        let unav = UNav::generate_shaped(seed, options.shape, options.size);
+        let failures = options.thresholds.failures(&unav.stats());
+        if failures.is_empty() {
+            continue;
+        }
+        rejected += 1;
+
+        let reroll = options
+            .thresholds
+            .reroll(seed, options.shape, options.size, options.rerolls);
+        match reroll {
+            Some(reroll) => println!("{}: {} -> {}", seed, failures.join(", "), reroll.seed),
+            None => {
+                unrecoverable += 1;
+                println!("{}: {} -> no accepted reroll", seed, failures.join(", "));
+            }
+        }
+    }
+
+    println!(
+        "{} of {} seeds rejected, {} without an accepted reroll",
+        rejected, count, unrecoverable
+    );
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("export") => export(&args[1..]),
+        Some("stats") => stats(&args[1..]),
+        Some("check") => check(&args[1..]),
+        _ => bail!(USAGE),
+    }
+}
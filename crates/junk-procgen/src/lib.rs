@@ -0,0 +1,9 @@
+//! Procedural generation shared by the galaxy map and in-system scenes.
+
+mod region;
+mod sampler;
+mod tile;
+
+pub use region::{Mask, Polygon, Region};
+pub use sampler::PoissonSampler;
+pub use tile::{tile_seed, TileSampler};
@@ -0,0 +1,87 @@
+use bevy_math::{Rect, Vec2};
+
+/// An area points can be sampled in.
+pub trait Region {
+    /// Box around the whole region, sampling and wrapping happen in it.
+    fn bounds(&self) -> Rect;
+
+    fn contains(&self, point: Vec2) -> bool;
+}
+
+impl Region for Rect {
+    fn bounds(&self) -> Rect {
+        *self
+    }
+
+    fn contains(&self, point: Vec2) -> bool {
+        // half-open, so tiles sharing an edge don't both get a point on it
+        point.x >= self.min.x
+            && point.x < self.max.x
+            && point.y >= self.min.y
+            && point.y < self.max.y
+    }
+}
+
+/// A closed polygon, in either winding order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Polygon(pub Vec<Vec2>);
+
+impl Region for Polygon {
+    fn bounds(&self) -> Rect {
+        let min = self.0.iter().copied().reduce(Vec2::min).unwrap_or_default();
+        let max = self.0.iter().copied().reduce(Vec2::max).unwrap_or_default();
+        Rect { min, max }
+    }
+
+    fn contains(&self, point: Vec2) -> bool {
+        // even-odd rule
+        let mut inside = false;
+        let Some(mut previous) = self.0.last().copied() else {
+            return false;
+        };
+        for &current in &self.0 {
+            if (current.y > point.y) != (previous.y > point.y)
+                && point.x
+                    < (previous.x - current.x) * (point.y - current.y) / (previous.y - current.y)
+                        + current.x
+            {
+                inside = !inside;
+            }
+            previous = current;
+        }
+        inside
+    }
+}
+
+/// Any shape given as a test, within a box.
+pub struct Mask<F: Fn(Vec2) -> bool> {
+    pub bounds: Rect,
+    pub contains: F,
+}
+
+impl<F: Fn(Vec2) -> bool> Region for Mask<F> {
+    fn bounds(&self) -> Rect {
+        self.bounds
+    }
+
+    fn contains(&self, point: Vec2) -> bool {
+        Region::contains(&self.bounds, point) && (self.contains)(point)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn polygons_contain_their_inside() {
+        let triangle = Polygon(vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(10.0, 0.0),
+            Vec2::new(0.0, 10.0),
+        ]);
+        assert!(triangle.contains(Vec2::new(2.0, 2.0)));
+        assert!(!triangle.contains(Vec2::new(8.0, 8.0)));
+        assert_eq!(triangle.bounds(), Rect::new(0.0, 0.0, 10.0, 10.0));
+    }
+}
@@ -0,0 +1,445 @@
+use std::{f32::consts::SQRT_2, sync::Arc};
+
+use bevy_math::{Rect, Vec2};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{region::Region, tile::TileSampler};
+
+/// How many random spots are tried for the first point before giving up.
+const MAX_START_ATTEMPTS: usize = 10_000;
+
+type Density = Arc<dyn Fn(Vec2) -> f32 + Send + Sync>;
+
+type Thinning = Arc<dyn Fn(Vec2) -> f64 + Send + Sync>;
+
+/// Poisson disk sampling with float output.
+///
+/// Points are at least `min_dist` apart, or further where a density
+/// function spreads them out. They fill any `Region`, optionally wrapping
+/// around its bounds so the result tiles seamlessly.
+///
+/// With `snapped` it gives the same points as the integer sampler the galaxy
+/// map and starfield were first generated with, so their seeds still
+/// reproduce.
+///
+/// ```
+/// # use bevy_math::{Rect, Vec2};
+/// # use junk_procgen::PoissonSampler;
+/// let points = PoissonSampler::new(Rect::new(0.0, 0.0, 100.0, 100.0), 10.0)
+///     .with_density(30.0, |point: Vec2| point.x / 100.0)
+///     .sample(7);
+/// assert!(!points.is_empty());
+/// ```
+#[derive(Clone)]
+pub struct PoissonSampler<R> {
+    region: R,
+    min_dist: f32,
+    max_dist: f32,
+    density: Option<Density>,
+    thinning: Option<Thinning>,
+    attempts: usize,
+    wrap: bool,
+    snap: bool,
+    refill: bool,
+    avoid: Vec<Vec2>,
+}
+
+impl<R: Region> PoissonSampler<R> {
+    pub fn new(region: R, min_dist: f32) -> Self {
+        // a zero distance would never finish sampling
+        let min_dist = min_dist.max(f32::EPSILON);
+        Self {
+            region,
+            min_dist,
+            max_dist: min_dist,
+            density: None,
+            thinning: None,
+            attempts: 30,
+            wrap: false,
+            snap: false,
+            refill: false,
+            avoid: Vec::new(),
+        }
+    }
+
+    /// Varies the spacing between points: `min_dist` where `density` is 1.0,
+    /// `max_dist` where it is 0.0.
+    pub fn with_density(
+        mut self,
+        max_dist: f32,
+        density: impl Fn(Vec2) -> f32 + Send + Sync + 'static,
+    ) -> Self {
+        self.max_dist = max_dist.max(self.min_dist);
+        self.density = Some(Arc::new(density));
+        self
+    }
+
+    /// Keeps each candidate only with probability `density(point)`, 0.0 to
+    /// 1.0. Unlike `with_density` the spacing stays the same, points are
+    /// just left out where it is low.
+    pub fn thinned(mut self, density: impl Fn(Vec2) -> f64 + Send + Sync + 'static) -> Self {
+        self.thinning = Some(Arc::new(density));
+        self
+    }
+
+    /// Number of candidates tried around each point, higher packs tighter.
+    pub fn attempts(mut self, attempts: usize) -> Self {
+        self.attempts = attempts.max(1);
+        self
+    }
+
+    /// Measures distances across the edges of the region's bounds, so the
+    /// points can be repeated side by side without seams.
+    pub fn wrapping(mut self) -> Self {
+        self.wrap = true;
+        self
+    }
+
+    /// Cuts points down to whole units from the region's min corner, for
+    /// callers that keep integer positions.
+    pub fn snapped(mut self) -> Self {
+        self.snap = true;
+        self
+    }
+
+    /// Once growth stops, scatters new starting points so parts of the
+    /// region it couldn't reach get filled too. There is one try per grid
+    /// cell for the whole run.
+    pub fn refilling(mut self) -> Self {
+        self.refill = true;
+        self
+    }
+
+    /// Keeps new points clear of existing ones, e.g. from a neighbouring
+    /// area sampled earlier.
+    pub fn avoiding(mut self, points: impl IntoIterator<Item = Vec2>) -> Self {
+        self.avoid.extend(points);
+        self
+    }
+
+    /// The same settings over another region.
+    pub fn with_region<S: Region>(self, region: S) -> PoissonSampler<S> {
+        PoissonSampler {
+            region,
+            min_dist: self.min_dist,
+            max_dist: self.max_dist,
+            density: self.density,
+            thinning: self.thinning,
+            attempts: self.attempts,
+            wrap: self.wrap,
+            snap: self.snap,
+            refill: self.refill,
+            avoid: self.avoid,
+        }
+    }
+
+    /// Samples square tiles of an endless plane, one at a time.
+    pub fn tiles(self, tile_size: f32) -> TileSampler {
+        TileSampler::new(self.with_region(Rect::default()), tile_size)
+    }
+
+    pub fn max_dist(&self) -> f32 {
+        self.max_dist
+    }
+
+    /// How far the nearest other point must be from `point`.
+    pub fn spacing(&self, point: Vec2) -> f32 {
+        match &self.density {
+            Some(density) => {
+                let density = density(point).clamp(0.0, 1.0);
+                self.max_dist + (self.min_dist - self.max_dist) * density
+            }
+            None => self.min_dist,
+        }
+    }
+
+    pub fn sample(&self, seed: u64) -> Vec<Vec2> {
+        Sampling::new(self).run(&mut StdRng::seed_from_u64(seed))
+    }
+
+    /// Rolls whether a candidate that fits is kept, only touching the rng
+    /// where it is thinned out.
+    fn keep(&self, rng: &mut StdRng, point: Vec2) -> bool {
+        match &self.thinning {
+            Some(thinning) => {
+                let density = thinning(point);
+                density >= 1.0 || (density > 0.0 && rng.gen::<f64>() < density)
+            }
+            None => true,
+        }
+    }
+}
+
+/// State of one run of the sampler.
+struct Sampling<'a, R> {
+    sampler: &'a PoissonSampler<R>,
+    bounds: Rect,
+    cell_size: f32,
+    cols: i32,
+    rows: i32,
+    /// Cells to search either side of a candidate.
+    reach: i32,
+    /// Index into `points` for each cell. Points are never closer than
+    /// `min_dist`, the cell diagonal, so a cell holds at most one.
+    grid: Vec<Option<usize>>,
+    points: Vec<Vec2>,
+    spacings: Vec<f32>,
+}
+
+impl<'a, R: Region> Sampling<'a, R> {
+    fn new(sampler: &'a PoissonSampler<R>) -> Self {
+        let bounds = sampler.region.bounds();
+        let cell_size = sampler.min_dist / SQRT_2;
+        let cols = ((bounds.width() / cell_size).ceil() as i32).max(1);
+        let rows = ((bounds.height() / cell_size).ceil() as i32).max(1);
+        Self {
+            sampler,
+            bounds,
+            cell_size,
+            cols,
+            rows,
+            reach: (sampler.max_dist / cell_size).ceil() as i32,
+            grid: vec![None; (cols * rows) as usize],
+            points: Vec::new(),
+            spacings: Vec::new(),
+        }
+    }
+
+    fn wrap(&self, point: Vec2) -> Vec2 {
+        if self.sampler.wrap {
+            self.bounds.min + (point - self.bounds.min).rem_euclid(self.bounds.size())
+        } else {
+            point
+        }
+    }
+
+    fn snap(&self, point: Vec2) -> Vec2 {
+        if self.sampler.snap {
+            self.bounds.min + (point - self.bounds.min).trunc()
+        } else {
+            point
+        }
+    }
+
+    fn distance_squared(&self, a: Vec2, b: Vec2) -> f32 {
+        let mut delta = b - a;
+        if self.sampler.wrap {
+            let size = self.bounds.size();
+            delta -= size * (delta / size).round();
+        }
+        delta.length_squared()
+    }
+
+    fn cell(&self, point: Vec2) -> (i32, i32) {
+        let cell = ((point - self.bounds.min) / self.cell_size).floor();
+        (
+            (cell.x as i32).clamp(0, self.cols - 1),
+            (cell.y as i32).clamp(0, self.rows - 1),
+        )
+    }
+
+    /// Whether `point` is far enough from every point so far.
+    fn clear(&self, point: Vec2, spacing: f32) -> bool {
+        let clear = |other: Vec2, other_spacing: f32| {
+            let spacing = spacing.max(other_spacing);
+            self.distance_squared(point, other) >= spacing * spacing
+        };
+        if !self
+            .sampler
+            .avoid
+            .iter()
+            .all(|other| clear(*other, self.sampler.spacing(*other)))
+        {
+            return false;
+        }
+
+        let (col, row) = self.cell(point);
+        for dx in -self.reach..=self.reach {
+            for dy in -self.reach..=self.reach {
+                let (mut x, mut y) = (col + dx, row + dy);
+                if self.sampler.wrap {
+                    x = x.rem_euclid(self.cols);
+                    y = y.rem_euclid(self.rows);
+                } else if x < 0 || y < 0 || x >= self.cols || y >= self.rows {
+                    continue;
+                }
+                if let Some(i) = self.grid[(x + y * self.cols) as usize] {
+                    if !clear(self.points[i], self.spacings[i]) {
+                        return false;
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    /// Adds `candidate` if it fits. Distances are measured from the
+    /// candidate itself, but with `snapped` the point kept is cut down to
+    /// whole units.
+    fn try_add(&mut self, rng: &mut StdRng, candidate: Vec2) -> Option<usize> {
+        let point = self.snap(candidate);
+        if !self.sampler.region.contains(point) {
+            return None;
+        }
+        let spacing = self.sampler.spacing(point);
+        if !self.clear(candidate, spacing) || !self.sampler.keep(rng, point) {
+            return None;
+        }
+
+        let (col, row) = self.cell(candidate);
+        self.points.push(point);
+        self.spacings.push(spacing);
+        let index = self.points.len() - 1;
+        self.grid[(col + row * self.cols) as usize] = Some(index);
+        Some(index)
+    }
+
+    /// A random point anywhere in the bounds that fits, using up `tries`.
+    fn scatter(&mut self, rng: &mut StdRng, tries: &mut usize) -> Option<usize> {
+        let (min, max) = (self.bounds.min, self.bounds.max);
+        if min.x >= max.x || min.y >= max.y {
+            return None;
+        }
+        while *tries > 0 {
+            *tries -= 1;
+            let point = Vec2::new(rng.gen_range(min.x..max.x), rng.gen_range(min.y..max.y));
+            if let Some(index) = self.try_add(rng, point) {
+                return Some(index);
+            }
+        }
+        None
+    }
+
+    fn run(mut self, rng: &mut StdRng) -> Vec<Vec2> {
+        let mut tries = MAX_START_ATTEMPTS;
+        let Some(start) = self.scatter(rng, &mut tries) else {
+            return self.points;
+        };
+        let mut active = vec![start];
+        let mut refills = if self.sampler.refill {
+            self.grid.len()
+        } else {
+            0
+        };
+
+        loop {
+            if active.is_empty() {
+                match self.scatter(rng, &mut refills) {
+                    Some(index) => active.push(index),
+                    None => break,
+                }
+            }
+
+            let pick = rng.gen_range(0..active.len());
+            let (point, spacing) = (self.points[active[pick]], self.spacings[active[pick]]);
+
+            let mut found = false;
+            for _ in 0..self.sampler.attempts {
+                let distance = rng.gen_range(spacing..2.0 * spacing);
+                let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+                let candidate = self.wrap(point + Vec2::from_angle(angle) * distance);
+                if let Some(index) = self.try_add(rng, candidate) {
+                    active.push(index);
+                    found = true;
+                    break;
+                }
+            }
+
+            // kept in order, so the picks that follow are the same as they
+            // always were for a seed
+            if !found {
+                active.remove(pick);
+            }
+        }
+
+        self.points
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::region::{Mask, Polygon};
+
+    fn min_distance(points: &[Vec2], distance: impl Fn(Vec2, Vec2) -> f32) -> f32 {
+        let mut min = f32::MAX;
+        for (i, a) in points.iter().enumerate() {
+            for b in &points[i + 1..] {
+                min = min.min(distance(*a, *b));
+            }
+        }
+        min
+    }
+
+    #[test]
+    fn points_keep_their_distance() {
+        let bounds = Rect::new(0.0, 0.0, 200.0, 200.0);
+        let points = PoissonSampler::new(bounds, 10.0).sample(1);
+        assert!(points.len() > 100);
+        assert!(points.iter().all(|p| bounds.contains(*p)));
+        assert!(min_distance(&points, Vec2::distance) >= 10.0);
+        assert_eq!(points, PoissonSampler::new(bounds, 10.0).sample(1));
+    }
+
+    #[test]
+    fn wrapping_keeps_distance_across_edges() {
+        let size = Vec2::splat(200.0);
+        let points = PoissonSampler::new(Rect::from_corners(Vec2::ZERO, size), 10.0)
+            .wrapping()
+            .sample(2);
+        let wrapped = |a: Vec2, b: Vec2| {
+            let delta = b - a;
+            (delta - size * (delta / size).round()).length()
+        };
+        assert!(min_distance(&points, wrapped) >= 10.0);
+    }
+
+    #[test]
+    fn density_thins_points_out() {
+        let bounds = Rect::new(0.0, 0.0, 400.0, 200.0);
+        let points = PoissonSampler::new(bounds, 8.0)
+            .with_density(32.0, |p: Vec2| if p.x < 200.0 { 1.0 } else { 0.0 })
+            .sample(3);
+        let dense = points.iter().filter(|p| p.x < 200.0).count();
+        assert!(dense > (points.len() - dense) * 4);
+
+        // thinned to nothing, the right half is left empty
+        let points = PoissonSampler::new(bounds, 8.0)
+            .thinned(|p: Vec2| if p.x < 200.0 { 1.0 } else { 0.0 })
+            .sample(3);
+        assert!(points.len() > 100);
+        assert!(points.iter().all(|p| p.x < 200.0));
+        assert!(min_distance(&points, Vec2::distance) >= 8.0);
+    }
+
+    #[test]
+    fn snapped_points_are_whole() {
+        let bounds = Rect::new(0.0, 0.0, 200.0, 200.0);
+        let points = PoissonSampler::new(bounds, 10.0).snapped().sample(6);
+        assert!(points.len() > 100);
+        assert!(points
+            .iter()
+            .all(|p| *p == p.trunc() && bounds.contains(*p)));
+    }
+
+    #[test]
+    fn points_stay_inside_shapes() {
+        let triangle = Polygon(vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(300.0, 0.0),
+            Vec2::new(0.0, 300.0),
+        ]);
+        let points = PoissonSampler::new(triangle.clone(), 10.0).sample(4);
+        assert!(points.len() > 50);
+        assert!(points.iter().all(|p| triangle.contains(*p)));
+
+        // two islands with nothing between them still both get filled
+        let islands = Mask {
+            bounds: Rect::new(0.0, 0.0, 300.0, 100.0),
+            contains: |p: Vec2| p.x < 50.0 || p.x > 250.0,
+        };
+        let points = PoissonSampler::new(islands, 10.0).refilling().sample(5);
+        assert!(points.iter().any(|p| p.x < 50.0));
+        assert!(points.iter().any(|p| p.x > 250.0));
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+
+use bevy_math::{IVec2, Rect, Vec2};
+
+use crate::sampler::PoissonSampler;
+
+/// Seed for one tile, so tiles can be generated in any order and come out
+/// the same.
+pub fn tile_seed(seed: u64, coord: IVec2) -> u64 {
+    let key = (coord.x as u32 as u64) << 32 | coord.y as u32 as u64;
+    let mut hash = seed ^ key;
+    // splitmix64 finaliser, neighbouring coords shouldn't give similar seeds
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    hash ^ (hash >> 31)
+}
+
+/// Poisson sampling of an endless plane, a square tile at a time, for
+/// worlds streamed in around the player.
+///
+/// Tiles are sampled in four phases by the parity of their coordinates,
+/// each one avoiding the points of its neighbours from earlier phases. A
+/// tile only ever depends on its neighbours, so it comes out the same
+/// whichever order tiles are asked for in, and there are no seams.
+pub struct TileSampler {
+    sampler: PoissonSampler<Rect>,
+    tile_size: f32,
+}
+
+impl TileSampler {
+    pub(crate) fn new(sampler: PoissonSampler<Rect>, tile_size: f32) -> Self {
+        // tiles of the same phase must be too far apart to clash
+        let tile_size = tile_size.max(sampler.max_dist());
+        Self { sampler, tile_size }
+    }
+
+    pub fn tile_size(&self) -> f32 {
+        self.tile_size
+    }
+
+    pub fn bounds(&self, coord: IVec2) -> Rect {
+        let min = coord.as_vec2() * self.tile_size;
+        Rect::from_corners(min, min + Vec2::splat(self.tile_size))
+    }
+
+    pub fn tile(&self, coord: IVec2, seed: u64) -> Vec<Vec2> {
+        self.sample(coord, seed, &mut HashMap::new())
+    }
+
+    /// Several tiles at once, sharing the work of their common neighbours.
+    pub fn tiles(
+        &self,
+        coords: impl IntoIterator<Item = IVec2>,
+        seed: u64,
+    ) -> Vec<(IVec2, Vec<Vec2>)> {
+        let mut cache = HashMap::new();
+        coords
+            .into_iter()
+            .map(|coord| (coord, self.sample(coord, seed, &mut cache)))
+            .collect()
+    }
+
+    fn sample(&self, coord: IVec2, seed: u64, cache: &mut HashMap<IVec2, Vec<Vec2>>) -> Vec<Vec2> {
+        if let Some(points) = cache.get(&coord) {
+            return points.clone();
+        }
+
+        let own_phase = phase(coord);
+        let mut avoid = Vec::new();
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                let neighbour = coord + IVec2::new(dx, dy);
+                if phase(neighbour) < own_phase {
+                    avoid.extend(self.sample(neighbour, seed, cache));
+                }
+            }
+        }
+
+        let points = self
+            .sampler
+            .clone()
+            .with_region(self.bounds(coord))
+            .avoiding(avoid)
+            .sample(tile_seed(seed, coord));
+        cache.insert(coord, points.clone());
+        points
+    }
+}
+
+fn phase(coord: IVec2) -> i32 {
+    coord.x.rem_euclid(2) + 2 * coord.y.rem_euclid(2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn neighbouring_tiles_get_different_seeds() {
+        let seeds: Vec<u64> = (-2..=2)
+            .flat_map(|x| (-2..=2).map(move |y| tile_seed(7, IVec2::new(x, y))))
+            .collect();
+        for (i, a) in seeds.iter().enumerate() {
+            assert!(!seeds[i + 1..].contains(a));
+        }
+        assert_eq!(tile_seed(7, IVec2::ONE), tile_seed(7, IVec2::ONE));
+    }
+
+    #[test]
+    fn tiles_match_up_in_any_order() {
+        let tiles = PoissonSampler::new(Rect::default(), 12.0).tiles(64.0);
+        let coords: Vec<IVec2> = (-2..=2)
+            .flat_map(|x| (-2..=2).map(move |y| IVec2::new(x, y)))
+            .collect();
+
+        let together = tiles.tiles(coords.iter().copied(), 3);
+        for (coord, points) in &together {
+            assert_eq!(points, &tiles.tile(*coord, 3));
+            assert!(points.iter().all(|p| tiles.bounds(*coord).contains(*p)));
+        }
+
+        let points: Vec<Vec2> = together.into_iter().flat_map(|(_, p)| p).collect();
+        for (i, a) in points.iter().enumerate() {
+            for b in &points[i + 1..] {
+                assert!(a.distance(*b) >= 12.0);
+            }
+        }
+    }
+}
@@ -11,6 +11,7 @@ mod outline;
 mod parts;
 mod ship;
 
+use junk_unav::{EncounterOdds, Faction};
 use outline::SpriteOutlineMaterial;
 pub use parts::*;
 use rand::Rng;
@@ -79,12 +80,24 @@ pub struct ShipPlugin;
 impl bevy::app::Plugin for ShipPlugin {
     fn build(&self, app: &mut bevy::app::App) {
         app.add_event::<SpawnShipEvent>()
+            .init_resource::<LocalFactions>()
+            .add_event::<DamageShipEvent>()
+            .add_event::<ShipDestroyed>()
             .init_resource::<PartsResource>()
+            .init_resource::<EncounterStrength>()
+            .init_resource::<ShipLighting>()
             .init_asset::<PartsAsset>()
             .init_asset_loader::<PartsAssetLoader>()
             .add_plugins(Material2dPlugin::<SpriteOutlineMaterial>::default())
             .add_systems(PostStartup, setup)
             .add_systems(Update, ship_spawner)
+            .add_systems(
+                Update,
+                light_ships
+                    .after(ship_spawner)
+                    .run_if(resource_changed::<ShipLighting>),
+            )
+            .add_systems(Update, damage_ships)
             .add_systems(Update, load_parts_resource)
             .add_systems(Update, player_startup);
     }
@@ -116,6 +129,39 @@ pub struct SpawnShipEvent {
     pub player: bool,
     pub position: Vec2,
     pub seed: u64,
+    pub strength: f32,
+    pub faction: Option<Faction>,
+}
+
+/// Strength of NPC ships spawned in the current system, 0.0 to 1.0.
+#[derive(Resource)]
+pub struct EncounterStrength(pub f32);
+
+impl Default for EncounterStrength {
+    fn default() -> Self {
+        Self(DEFAULT_SHIP_STRENGTH)
+    }
+}
+
+/// Odds of meeting each faction's ships in the current system.
+#[derive(Resource)]
+pub struct LocalFactions(pub EncounterOdds);
+
+impl Default for LocalFactions {
+    fn default() -> Self {
+        Self(EncounterOdds::for_owner(None))
+    }
+}
+
+/// Ambient light in the current system. Ships are unlit, so it's multiplied
+/// into their material's tint.
+#[derive(Resource)]
+pub struct ShipLighting(pub Color);
+
+impl Default for ShipLighting {
+    fn default() -> Self {
+        Self(Color::WHITE)
+    }
 }
 
 #[derive(Component)]
@@ -126,6 +172,45 @@ pub struct ShipComponent {
 #[derive(Component)]
 pub struct PlayerShip;
 
+/// Integrity every hull starts with before armor.
+const BASE_HULL: u32 = 100;
+
+/// How much more punishment a ship can take. Armor adds to it.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Hull {
+    pub integrity: u32,
+    pub max: u32,
+}
+
+impl Hull {
+    pub fn new(metrics: &ShipMetrics) -> Self {
+        let max = BASE_HULL + metrics.armor as u32;
+        Self {
+            integrity: max,
+            max,
+        }
+    }
+
+    /// Takes `amount` off the hull, returning whether it held.
+    pub fn damage(&mut self, amount: u32) -> bool {
+        self.integrity = self.integrity.saturating_sub(amount);
+        self.integrity > 0
+    }
+}
+
+#[derive(Event)]
+pub struct DamageShipEvent {
+    pub ship: Entity,
+    pub amount: u32,
+}
+
+/// A ship's hull gave out and it was despawned.
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ShipDestroyed {
+    pub ship: Entity,
+    pub player: bool,
+}
+
 #[derive(Component)]
 pub struct PartInfoComponent {
     pub part: PartInfo,
@@ -138,13 +223,20 @@ fn ship_spawner(
     mut materials: ResMut<Assets<SpriteOutlineMaterial>>,
     mut spawn_ship_event: EventReader<SpawnShipEvent>,
     asset_server: Res<AssetServer>,
+    lighting: Res<ShipLighting>,
 ) {
     for event in spawn_ship_event.read() {
-        let ship = Ship::generate(event.seed, parts_resource.all_parts());
+        let ship = Ship::generate_with_strength(
+            event.seed,
+            parts_resource.all_parts(),
+            event.strength,
+            event.faction,
+        );
         let ship_component = ShipComponent { ship: ship.clone() };
         let transform =
             Transform::from_translation(Vec3::new(event.position.x, event.position.y, 0.0));
-        let mut entity_commands = commands.spawn((ship_component, transform));
+        let hull = Hull::new(&ship.metrics(parts_resource.all_parts()));
+        let mut entity_commands = commands.spawn((ship_component, hull, transform));
         if event.player {
             entity_commands.insert(PlayerShip);
         }
@@ -155,10 +247,44 @@ fn ship_spawner(
             &asset_server,
             parts_resource.all_parts(),
             &ship,
+            lighting.0,
         );
     }
 }
 
+// ships whose hull gives out are gone, the rest of the game hears about it
+fn damage_ships(
+    mut commands: Commands,
+    mut damage_events: EventReader<DamageShipEvent>,
+    mut hulls: Query<(&mut Hull, Has<PlayerShip>)>,
+    mut destroyed_events: EventWriter<ShipDestroyed>,
+) {
+    for event in damage_events.read() {
+        let Ok((mut hull, player)) = hulls.get_mut(event.ship) else {
+            warn!("Damage for {:?}, which has no hull", event.ship);
+            continue;
+        };
+        // already gone, despawning waits for the end of the frame
+        if hull.integrity == 0 {
+            continue;
+        }
+        if hull.damage(event.amount) {
+            continue;
+        }
+
+        if player {
+            warn!("The player's hull gave out");
+        } else {
+            info!("Hull of {:?} gave out", event.ship);
+        }
+        commands.entity(event.ship).despawn_recursive();
+        destroyed_events.send(ShipDestroyed {
+            ship: event.ship,
+            player,
+        });
+    }
+}
+
 fn build_ship(
     entity_commands: &mut EntityCommands,
     meshes: &mut ResMut<Assets<Mesh>>,
@@ -166,6 +292,7 @@ fn build_ship(
     asset_server: &Res<AssetServer>,
     parts: &HashSet<PartInfo>,
     ship: &Ship,
+    light: Color,
 ) {
     let ship_mesh = ship.mesh(parts);
     let mesh = meshes.add(ship_mesh);
@@ -173,7 +300,7 @@ fn build_ship(
     let texture_handle = asset_server.load("textures/ship_dev_v2.png");
 
     let material = materials.add(SpriteOutlineMaterial {
-        color: Vec4::new(1.0, 1.0, 1.0, 1.0),         // White tint
+        color: LinearRgba::from(light).to_vec4(),     // Ambient tint
         outline_color: Vec4::new(0.0, 0.0, 0.0, 1.0), // Black outline
         outline_thickness: 0.005,                     // Adjust based on texture size
         main_texture: texture_handle.clone(),
@@ -182,8 +309,23 @@ fn build_ship(
     entity_commands.with_child((Mesh2d(mesh), MeshMaterial2d(material)));
 }
 
+// relights the ships already out there when the player moves on
+fn light_ships(
+    lighting: Res<ShipLighting>,
+    ships: Query<&MeshMaterial2d<SpriteOutlineMaterial>>,
+    mut materials: ResMut<Assets<SpriteOutlineMaterial>>,
+) {
+    for handle in ships.iter() {
+        if let Some(material) = materials.get_mut(&handle.0) {
+            material.color = LinearRgba::from(lighting.0).to_vec4();
+        }
+    }
+}
+
 fn player_startup(
     input: Res<ButtonInput<KeyCode>>,
+    local_factions: Res<LocalFactions>,
+    encounter_strength: Res<EncounterStrength>,
     mut spawn_ship_event: EventWriter<SpawnShipEvent>,
 ) {
     if input.just_pressed(KeyCode::Enter) {
@@ -191,6 +333,8 @@ fn player_startup(
             player: true,
             position: Vec2::new(0.0, 0.0),
             seed: 15,
+            faction: None,
+            strength: DEFAULT_SHIP_STRENGTH,
         });
 
         for i in 0..40 {
@@ -202,6 +346,8 @@ fn player_startup(
                     rand.gen_range(-1000.0..1000.0),
                 ),
                 seed: i,
+                faction: Some(local_factions.0.pick(rand.gen())),
+                strength: encounter_strength.0,
             });
         }
     }
@@ -213,6 +359,8 @@ mod tests {
     use rand::SeedableRng;
 
     use crate::ship::*;
+    use crate::PartType;
+    use junk_unav::Faction;
 
     #[test]
     fn test_ship_id_generate() {
@@ -229,4 +377,99 @@ mod tests {
 
     #[test]
     fn test_ship() {}
+
+    #[test]
+    fn test_faction_part_preferences() {
+        let hull = PartType::Hull {
+            armor: 1,
+            cargo_capacity: 16,
+        };
+        let cargo = PartType::Cargo { cargo_capacity: 64 };
+        assert!(part_weight(Faction::Traders, &cargo) > part_weight(Faction::Traders, &hull));
+        assert!(part_weight(Faction::Pirates, &hull) > part_weight(Faction::Pirates, &cargo));
+    }
+
+    #[test]
+    fn ships_take_on_the_system_light() {
+        use crate::{light_ships, ShipLighting, SpriteOutlineMaterial};
+        use bevy::prelude::*;
+
+        let mut app = App::new();
+        app.init_resource::<Assets<SpriteOutlineMaterial>>()
+            .insert_resource(ShipLighting(Color::srgb(1.0, 0.5, 0.25)))
+            .add_systems(Update, light_ships);
+        let material = app
+            .world_mut()
+            .resource_mut::<Assets<SpriteOutlineMaterial>>()
+            .add(SpriteOutlineMaterial {
+                color: Vec4::ONE,
+                outline_color: Vec4::W,
+                outline_thickness: 0.005,
+                main_texture: Handle::default(),
+            });
+        app.world_mut().spawn(MeshMaterial2d(material.clone()));
+        app.update();
+
+        let materials = app.world().resource::<Assets<SpriteOutlineMaterial>>();
+        let color = materials.get(&material).unwrap().color;
+        assert_eq!(
+            color,
+            LinearRgba::from(Color::srgb(1.0, 0.5, 0.25)).to_vec4()
+        );
+    }
+
+    #[test]
+    fn test_hull_damage() {
+        let metrics = ShipMetrics {
+            crew_capacity: 1,
+            armor: 20,
+            cargo_capacity: 0,
+            thrust: 0,
+            weight: 10,
+        };
+        let mut hull = crate::Hull::new(&metrics);
+        assert_eq!(hull.integrity, 120);
+        assert!(hull.damage(100));
+        assert!(!hull.damage(100));
+        assert_eq!(hull.integrity, 0);
+    }
+
+    #[test]
+    fn wrecked_ships_are_despawned() {
+        use crate::{damage_ships, DamageShipEvent, Hull, ShipDestroyed};
+        use bevy::prelude::*;
+
+        let mut app = App::new();
+        app.add_event::<DamageShipEvent>()
+            .add_event::<ShipDestroyed>()
+            .add_systems(Update, damage_ships);
+        let hull = Hull {
+            integrity: 50,
+            max: 50,
+        };
+        let ship = app.world_mut().spawn(hull).id();
+
+        app.world_mut()
+            .send_event(DamageShipEvent { ship, amount: 30 });
+        app.update();
+        assert_eq!(app.world().get::<Hull>(ship).unwrap().integrity, 20);
+
+        // a second hit the same frame doesn't wreck it twice
+        app.world_mut()
+            .send_event(DamageShipEvent { ship, amount: 30 });
+        app.world_mut()
+            .send_event(DamageShipEvent { ship, amount: 30 });
+        app.update();
+        assert!(app.world().get_entity(ship).is_err());
+
+        let events = app.world().resource::<Events<ShipDestroyed>>();
+        let destroyed: Vec<ShipDestroyed> = events.get_cursor().read(events).copied().collect();
+        assert_eq!(
+            destroyed,
+            vec![ShipDestroyed {
+                ship,
+                player: false
+            }]
+        );
+    }
 }
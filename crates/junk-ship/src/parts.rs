@@ -1,8 +1,4 @@
-use std::{
-    collections::{HashMap, HashSet},
-    fs::File,
-    io::{BufReader, Read},
-};
+use std::collections::{HashMap, HashSet};
 
 use bevy::math::{I8Vec2, U8Vec2};
 use serde::{Deserialize, Serialize};
@@ -1,10 +1,9 @@
+#![allow(clippy::needless_range_loop)]
+
+use bevy::math::UVec2;
 use rand::distributions::WeightedIndex;
 use rand::prelude::*;
-use std::collections::{HashMap, HashSet};
-
-// Constants defining the grid size
-const MAX_X: usize = 33;
-const MAX_Y: usize = 33;
+use std::collections::{BTreeMap, BTreeSet};
 
 // Enum representing the different types of cells in the grid
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
@@ -17,34 +16,37 @@ pub enum CellType {
 
 // Struct representing the cellular automata grid
 pub struct Automata {
-    cells: [[CellType; MAX_Y]; MAX_X],
-    active: HashSet<(usize, usize)>, // Active cells to process
+    rng: StdRng,
+    size: (usize, usize),
+    cells: Vec<Vec<CellType>>,
+    active: BTreeSet<(usize, usize)>, // Active cells to process, ordered so runs repeat
 }
 
 impl Automata {
     /// Creates a new Automata instance with all cells initialized to Empty,
     /// except the center cell which is set to Cockpit.
-    pub fn new() -> Self {
-        let mut cells = [[CellType::Empty; MAX_Y]; MAX_X];
+    /// `walk_length` is the length of the initial random walk, longer walks
+    /// seed more hull cells and grow bigger ships. The same `rng` state and
+    /// `size` always grow the same shape.
+    pub fn new(mut rng: StdRng, size: UVec2, walk_length: usize) -> Self {
+        let (max_x, max_y) = (size.x as usize, size.y as usize);
+        let mut cells = vec![vec![CellType::Empty; max_y]; max_x];
 
         // Place the cockpit at the center of the grid
-        let center_x = MAX_X / 2;
-        let center_y = MAX_Y / 2;
+        let center_x = max_x / 2;
+        let center_y = max_y / 2;
         cells[center_x][center_y] = CellType::Cockpit;
 
         // Perform a weighted random walk to create initial hull cells
-        let mut rng = thread_rng();
-        let walk_length = 12; // Adjust based on desired ship size
         let mut x = center_x as isize;
         let mut y = center_y as isize;
 
-        // Define directions: 0 = North, 1 = South, 2 = East, 3 = West
-        let directions = ["North", "South", "East", "West"];
+        // Directions: 0 = North, 1 = South, 2 = East, 3 = West
         // Assign weights: North less likely
         let weights = [1, 3, 6, 6]; // Adjust weights as desired
 
         // Create a WeightedIndex distribution
-        let mut dist = WeightedIndex::new(&weights).unwrap();
+        let dist = WeightedIndex::new(weights).unwrap();
 
         for _ in 0..walk_length {
             let reset = rng.gen_range(0..20);
@@ -64,31 +66,33 @@ impl Automata {
             };
 
             // Update coordinates based on direction
-            match direction {
-                0 => y -= 1, // North
-                1 => y += 1, // South
-                2 => x += 1, // East
-                3 => x -= 1, // West
-                _ => {}
-            }
+            let (nx, ny) = match direction {
+                0 => (x, y - 1), // North
+                1 => (x, y + 1), // South
+                2 => (x + 1, y), // East
+                3 => (x - 1, y), // West
+                _ => (x, y),
+            };
 
-            // Ensure the walk stays within bounds
-            if x < 0 || x >= MAX_X as isize || y < 0 || y >= MAX_Y as isize {
+            // Ensure the walk stays within bounds, it stays put at the edge
+            if nx < 0 || nx >= max_x as isize || ny < 0 || ny >= max_y as isize {
                 continue;
             }
+            (x, y) = (nx, ny);
 
             let (ux, uy) = (x as usize, y as usize);
 
             // Mark the cell as Hull if it's Empty and check cell below is not Cockpit
-            if cells[ux][uy] == CellType::Empty && cells[ux][uy + 1] != CellType::Cockpit {
+            if cells[ux][uy] == CellType::Empty && cells[ux].get(uy + 1) != Some(&CellType::Cockpit)
+            {
                 cells[ux][uy] = CellType::Hull;
             }
         }
 
         // Initialize active list with hull cells and their neighbors
-        let mut active = HashSet::new();
-        for ux in 0..MAX_X {
-            for uy in 0..MAX_Y {
+        let mut active = BTreeSet::new();
+        for ux in 0..max_x {
+            for uy in 0..max_y {
                 if cells[ux][uy] == CellType::Hull || cells[ux][uy] == CellType::Cockpit {
                     // Add all neighbors to active list
                     for dx in -1..=1 {
@@ -98,7 +102,7 @@ impl Automata {
                             }
                             let nx = ux as isize + dx;
                             let ny = uy as isize + dy;
-                            if nx >= 0 && nx < MAX_X as isize && ny >= 0 && ny < MAX_Y as isize {
+                            if nx >= 0 && nx < max_x as isize && ny >= 0 && ny < max_y as isize {
                                 active.insert((nx as usize, ny as usize));
                             }
                         }
@@ -107,7 +111,12 @@ impl Automata {
             }
         }
 
-        Automata { cells, active }
+        Automata {
+            rng,
+            size: (max_x, max_y),
+            cells,
+            active,
+        }
     }
 
     /// Runs the cellular automata for a specified number of iterations.
@@ -125,8 +134,6 @@ impl Automata {
         let current_active: Vec<(usize, usize)> = self.active.iter().cloned().collect();
         self.active.clear();
 
-        let mut rng = thread_rng();
-
         for (x, y) in current_active {
             let neighbors = self.count_neighbors(x, y);
             let current_cell = self.cells[x][y];
@@ -134,8 +141,8 @@ impl Automata {
             match current_cell {
                 CellType::Empty => {
                     // and check cell below is not Cockpit
-                    if neighbors >= 3 && self.cells[x][y + 1] != CellType::Cockpit {
-                        let chance = rng.gen_range(0..10);
+                    if neighbors >= 3 && self.cells[x].get(y + 1) != Some(&CellType::Cockpit) {
+                        let chance = self.rng.gen_range(0..10);
                         if chance < 4 {
                             changes.push(((x, y), CellType::Hull));
                         }
@@ -143,7 +150,7 @@ impl Automata {
                 }
                 CellType::Hull => {
                     if neighbors <= 1 {
-                        let chance = rng.gen_range(0..10);
+                        let chance = self.rng.gen_range(0..10);
                         if chance < 4 {
                             changes.push(((x, y), CellType::Empty));
                         }
@@ -169,7 +176,7 @@ impl Automata {
                     }
                     let nx = *x as isize + dx;
                     let ny = *y as isize + dy;
-                    if nx >= 0 && nx < MAX_X as isize && ny >= 0 && ny < MAX_Y as isize {
+                    if self.in_bounds(nx, ny) {
                         self.active.insert((nx as usize, ny as usize));
                     }
                 }
@@ -177,6 +184,10 @@ impl Automata {
         }
     }
 
+    fn in_bounds(&self, x: isize, y: isize) -> bool {
+        x >= 0 && x < self.size.0 as isize && y >= 0 && y < self.size.1 as isize
+    }
+
     /// Counts the number of neighboring cells that are either Hull or Cockpit.
     fn count_neighbors(&self, x: usize, y: usize) -> usize {
         let mut count = 0;
@@ -194,7 +205,7 @@ impl Automata {
         for &(dx, dy) in &directions {
             let nx = x as isize + dx;
             let ny = y as isize + dy;
-            if nx >= 0 && nx < MAX_X as isize && ny >= 0 && ny < MAX_Y as isize {
+            if self.in_bounds(nx, ny) {
                 let neighbor = self.cells[nx as usize][ny as usize];
                 if neighbor == CellType::Hull || neighbor == CellType::Cockpit {
                     count += 1;
@@ -207,8 +218,8 @@ impl Automata {
 
     /// Post-processes the grid after CA iterations, such as placing engines.
     fn post_process(&mut self) {
-        let bottom_y = MAX_Y - 1;
-        for x in 0..MAX_X {
+        let bottom_y = self.size.1 - 1;
+        for x in 0..self.size.0 {
             if self.cells[x][bottom_y] == CellType::Hull {
                 self.cells[x][bottom_y] = CellType::Engine;
             }
@@ -217,13 +228,13 @@ impl Automata {
 
     /// Validates that all Hull and Engine cells are connected to the Cockpit.
     pub fn validate_connectivity(&self) -> bool {
-        let mut visited = [[false; MAX_Y]; MAX_X];
+        let mut visited = vec![vec![false; self.size.1]; self.size.0];
         let mut queue = std::collections::VecDeque::new();
 
         // Find the Cockpit position
         let mut cockpit_found = false;
-        for x in 0..MAX_X {
-            for y in 0..MAX_Y {
+        for x in 0..self.size.0 {
+            for y in 0..self.size.1 {
                 if self.cells[x][y] == CellType::Cockpit {
                     queue.push_back((x, y));
                     visited[x][y] = true;
@@ -257,7 +268,7 @@ impl Automata {
                 let nx = x as isize + dx;
                 let ny = y as isize + dy;
 
-                if nx >= 0 && nx < MAX_X as isize && ny >= 0 && ny < MAX_Y as isize {
+                if self.in_bounds(nx, ny) {
                     let nx = nx as usize;
                     let ny = ny as usize;
                     if !visited[nx][ny]
@@ -272,8 +283,8 @@ impl Automata {
         }
 
         // Check if any Hull or Engine cells are not visited
-        for x in 0..MAX_X {
-            for y in 0..MAX_Y {
+        for x in 0..self.size.0 {
+            for y in 0..self.size.1 {
                 if (self.cells[x][y] == CellType::Hull || self.cells[x][y] == CellType::Engine)
                     && !visited[x][y]
                 {
@@ -291,13 +302,13 @@ impl Automata {
             return; // All cells are connected; nothing to do
         }
 
-        let mut visited = [[false; MAX_Y]; MAX_X];
+        let mut visited = vec![vec![false; self.size.1]; self.size.0];
         let mut queue = std::collections::VecDeque::new();
 
         // Find the Cockpit position
         let mut cockpit_found = false;
-        for x in 0..MAX_X {
-            for y in 0..MAX_Y {
+        for x in 0..self.size.0 {
+            for y in 0..self.size.1 {
                 if self.cells[x][y] == CellType::Cockpit {
                     queue.push_back((x, y));
                     visited[x][y] = true;
@@ -312,8 +323,8 @@ impl Automata {
 
         if !cockpit_found {
             // If there's no Cockpit, clear all cells
-            for x in 0..MAX_X {
-                for y in 0..MAX_Y {
+            for x in 0..self.size.0 {
+                for y in 0..self.size.1 {
                     self.cells[x][y] = CellType::Empty;
                 }
             }
@@ -337,7 +348,7 @@ impl Automata {
                 let nx = x as isize + dx;
                 let ny = y as isize + dy;
 
-                if nx >= 0 && nx < MAX_X as isize && ny >= 0 && ny < MAX_Y as isize {
+                if self.in_bounds(nx, ny) {
                     let nx = nx as usize;
                     let ny = ny as usize;
                     if !visited[nx][ny]
@@ -352,8 +363,8 @@ impl Automata {
         }
 
         // Remove any cells that weren't visited (i.e., disconnected)
-        for x in 0..MAX_X {
-            for y in 0..MAX_Y {
+        for x in 0..self.size.0 {
+            for y in 0..self.size.1 {
                 if (self.cells[x][y] == CellType::Hull || self.cells[x][y] == CellType::Engine)
                     && !visited[x][y]
                 {
@@ -364,9 +375,10 @@ impl Automata {
     }
 
     /// Displays the current state of the grid in the console for debugging.
+    #[allow(dead_code)]
     pub fn display(&self) {
-        for y in 0..MAX_Y {
-            for x in 0..MAX_X {
+        for y in 0..self.size.1 {
+            for x in 0..self.size.0 {
                 let symbol = match self.cells[x][y] {
                     CellType::Empty => '.',
                     CellType::Cockpit => 'C',
@@ -379,10 +391,10 @@ impl Automata {
         }
     }
 
-    pub fn get_non_empty(&self) -> HashMap<(usize, usize), CellType> {
-        let mut non_empty = HashMap::new();
-        for x in 0..MAX_X {
-            for y in 0..MAX_Y {
+    pub fn get_non_empty(&self) -> BTreeMap<(usize, usize), CellType> {
+        let mut non_empty = BTreeMap::new();
+        for x in 0..self.size.0 {
+            for y in 0..self.size.1 {
                 if self.cells[x][y] != CellType::Empty {
                     non_empty.insert((x, y), self.cells[x][y]);
                 }
@@ -396,9 +408,13 @@ impl Automata {
 mod tests {
     use super::*;
 
+    fn automata(seed: u64, size: UVec2) -> Automata {
+        Automata::new(StdRng::seed_from_u64(seed), size, 12)
+    }
+
     #[test]
     fn automata_run() {
-        let mut automata = Automata::new();
+        let mut automata = automata(0, UVec2::splat(33));
         automata.run(8);
         automata.display();
         assert!(automata.validate_connectivity());
@@ -407,10 +423,21 @@ mod tests {
     #[test]
     fn batch_test() {
         for i in 0..100 {
-            let mut automata = Automata::new();
+            let mut automata = automata(i as u64, UVec2::splat(33));
             automata.run(i);
             automata.display();
             assert!(automata.validate_connectivity());
         }
     }
+
+    #[test]
+    fn same_seed_same_shape() {
+        let grow = |seed| {
+            let mut automata = automata(seed, UVec2::new(21, 17));
+            automata.run(7);
+            automata.get_non_empty()
+        };
+        assert_eq!(grow(5), grow(5));
+        assert!(grow(5).keys().all(|(x, y)| *x < 21 && *y < 17));
+    }
 }
@@ -5,7 +5,7 @@ use std::{
 
 use bevy::math::{I8Vec2, IVec2, UVec2};
 use bevy_mesh::Mesh;
-use cellular_automata::CellType;
+use junk_unav::Faction;
 use rand::{
     rngs::StdRng,
     seq::{IteratorRandom, SliceRandom},
@@ -14,11 +14,20 @@ use rand::{
 
 mod cellular_automata;
 
+pub use cellular_automata::{Automata, CellType};
+
 use crate::{mesh::MeshPart, parts::*};
 
 pub const SHIP_ID_ALPHABET: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
 pub const SHIP_ID_LENGTH: usize = 6;
 
+/// Strength used by `Ship::generate`, gives the classic 12 step walk.
+pub const DEFAULT_SHIP_STRENGTH: f32 = 0.5;
+const MIN_WALK_LENGTH: usize = 4;
+const MAX_WALK_LENGTH: usize = 20;
+/// Cells the automata grows a ship in.
+const SHIP_GRID: UVec2 = UVec2::new(33, 33);
+
 #[derive(Debug, Clone)]
 pub struct ShipId(pub String);
 
@@ -26,7 +35,7 @@ impl ShipId {
     pub fn generate(rng: &mut StdRng) -> Self {
         let id: String = (0..SHIP_ID_LENGTH)
             .map(|_| {
-                let idx = rng.gen_range(0..SHIP_ID_ALPHABET.len()) as usize;
+                let idx = rng.gen_range(0..SHIP_ID_ALPHABET.len());
                 SHIP_ID_ALPHABET.chars().nth(idx).unwrap()
             })
             .collect();
@@ -68,6 +77,17 @@ impl Display for ShipMetrics {
     }
 }
 
+/// How strongly `faction` favours a part type when fitting out a hull:
+/// traders fill their ships with cargo holds, pirates and authorities with
+/// armoured hull.
+pub fn part_weight(faction: Faction, part_type: &PartType) -> u32 {
+    match (faction, part_type) {
+        (Faction::Traders, PartType::Cargo { .. }) => 4,
+        (Faction::Pirates | Faction::Authorities, PartType::Hull { .. }) => 3,
+        _ => 1,
+    }
+}
+
 /// ----------------------------------------
 /// Ship struct
 /// ----------------------------------------
@@ -88,14 +108,34 @@ impl Ship {
     /// Generate a new Ship with a random "walk" approach.
     /// We'll place in the order: cockpit -> cargo/hull -> engine.
     pub fn generate(seed: u64, parts: &HashSet<PartInfo>) -> Self {
+        Ship::generate_with_strength(seed, parts, DEFAULT_SHIP_STRENGTH, None)
+    }
+
+    /// Like `generate`, with `strength` (0.0 to 1.0) controlling how big the
+    /// ship grows and `faction` how it is fitted out.
+    pub fn generate_with_strength(
+        seed: u64,
+        parts: &HashSet<PartInfo>,
+        strength: f32,
+        faction: Option<Faction>,
+    ) -> Self {
         let mut rng = StdRng::seed_from_u64(seed);
         let id = ShipId::generate(&mut rng);
-        Ship::new(id).cellular(seed, parts)
+        let strength = strength.clamp(0.0, 1.0);
+        let walk_length = MIN_WALK_LENGTH
+            + ((MAX_WALK_LENGTH - MIN_WALK_LENGTH) as f32 * strength).round() as usize;
+        Ship::new(id).cellular(seed, parts, walk_length, faction)
     }
 
-    pub fn cellular(mut self, seed: u64, parts: &HashSet<PartInfo>) -> Self {
+    pub fn cellular(
+        mut self,
+        seed: u64,
+        parts: &HashSet<PartInfo>,
+        walk_length: usize,
+        faction: Option<Faction>,
+    ) -> Self {
         let mut rng = StdRng::seed_from_u64(seed);
-        let mut automata = cellular_automata::Automata::new();
+        let mut automata = Automata::new(StdRng::seed_from_u64(rng.gen()), SHIP_GRID, walk_length);
         automata.run(7);
 
         let cells = automata.get_non_empty();
@@ -103,16 +143,16 @@ impl Ship {
 
         let directions = |x: usize, y: usize| -> Vec<Direction> {
             let mut directions = Vec::new();
-            if let Some(_) = lookup.get(&(x, y + 1)) {
+            if lookup.contains_key(&(x, y + 1)) {
                 directions.push(Direction::Up);
             }
-            if let Some(_) = lookup.get(&(x, y - 1)) {
+            if lookup.contains_key(&(x, y - 1)) {
                 directions.push(Direction::Down);
             }
-            if let Some(_) = lookup.get(&(x - 1, y)) {
+            if lookup.contains_key(&(x - 1, y)) {
                 directions.push(Direction::Left);
             }
-            if let Some(_) = lookup.get(&(x + 1, y)) {
+            if lookup.contains_key(&(x + 1, y)) {
                 directions.push(Direction::Right);
             }
             directions
@@ -136,7 +176,7 @@ impl Ship {
                     let directions = directions(position.0, position.1);
                     let part = Ship::find_parts_with_only_directions(parts, directions);
                     if !part.is_empty() {
-                        let part = part.choose(&mut rng).unwrap();
+                        let part = Ship::choose_part(&mut rng, &part, faction);
                         self.place_part(
                             part,
                             I8Vec2::new(
@@ -154,7 +194,7 @@ impl Ship {
                                 Direction::Right,
                             ],
                         );
-                        let part = part.choose(&mut rng).unwrap();
+                        let part = Ship::choose_part(&mut rng, &part, faction);
                         self.place_part(
                             part,
                             I8Vec2::new(
@@ -171,6 +211,21 @@ impl Ship {
         self
     }
 
+    /// Picks one of `candidates`, leaning towards the part types `faction`
+    /// prefers. Ships without a faction pick uniformly.
+    fn choose_part<'a>(
+        rng: &mut StdRng,
+        candidates: &'a [PartInfo],
+        faction: Option<Faction>,
+    ) -> &'a PartInfo {
+        match faction {
+            Some(faction) => candidates
+                .choose_weighted(rng, |part| part_weight(faction, &part.properties.part_type))
+                .unwrap(),
+            None => candidates.choose(rng).unwrap(),
+        }
+    }
+
     pub fn random(&mut self, seed: u64, parts: &HashSet<PartInfo>, parts_count: usize) {
         let mut rng = StdRng::seed_from_u64(seed);
 
@@ -290,7 +345,10 @@ impl Ship {
                 // not a cockpit
                 !matches!(p.properties.part_type, PartType::Cockpit { .. })
             });
-        parts.cloned().collect()
+        // sorted, so seeded picks don't depend on the set's hash order
+        let mut parts: Vec<PartInfo> = parts.cloned().collect();
+        parts.sort_by_key(|p| p.id);
+        parts
     }
 
     pub fn get_directions(&self, current: I8Vec2, parts: &HashSet<PartInfo>) -> Vec<Direction> {
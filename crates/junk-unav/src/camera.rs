@@ -0,0 +1,309 @@
+use bevy::{
+    input::mouse::{MouseMotion, MouseScrollUnit, MouseWheel},
+    prelude::*,
+    window::PrimaryWindow,
+};
+
+use crate::{CurrentSystem, UNav, UNavToggle};
+
+pub const MIN_ZOOM: f32 = 0.05;
+/// Higher than scrolling used to allow (0.7) so "fit whole map" can frame
+/// Medium and Large galaxies.
+pub const MAX_ZOOM: f32 = 4.0;
+
+/// Zoom factor applied per scroll "line" (mouse wheel notch).
+const LINE_ZOOM_STEP: f32 = 0.1;
+/// Zoom factor applied per scrolled pixel (trackpads).
+const PIXEL_ZOOM_STEP: f32 = 0.002;
+/// Keyboard panning speed in screen pixels per second.
+const KEYBOARD_PAN_SPEED: f32 = 600.0;
+/// Extra space left around the map when framing it.
+const FIT_MARGIN: f32 = 1.1;
+
+#[derive(Component)]
+pub struct UNavCamera {
+    pub zoom: f32,
+    /// Where the map was left when it was last closed.
+    map_view: Vec2,
+    /// Transform and scale of the game view while the map is open.
+    world_view: Option<(Transform, f32)>,
+}
+
+#[derive(Component)]
+pub struct MainCamera;
+
+/// Framing commands for the UNav camera, sent by key bindings or other plugins.
+#[derive(Event, Clone, Copy, Debug)]
+pub enum UNavCameraCommand {
+    CenterOnCurrentSystem,
+    FitMap,
+}
+
+pub(crate) fn setup_camera(mut commands: Commands) {
+    commands.spawn((
+        Camera2d,
+        OrthographicProjection::default_2d(),
+        Transform::from_xyz(0.0, 0.0, 1.0),
+        UNavCamera {
+            zoom: 0.5,
+            map_view: Vec2::ZERO,
+            world_view: None,
+        },
+        MainCamera,
+    ));
+}
+
+/// The map and the game share one camera: stash the game view when the map
+/// opens and put it back when the map closes, so panning and zooming the map
+/// never moves the ship view or the starfield behind it.
+pub(crate) fn swap_view(
+    toggle: Res<UNavToggle>,
+    mut query: Query<(&mut UNavCamera, &mut Transform, &mut OrthographicProjection)>,
+) {
+    for (mut camera, mut transform, mut projection) in query.iter_mut() {
+        match (toggle.active, camera.world_view) {
+            (true, None) => {
+                camera.world_view = Some((*transform, projection.scale));
+                transform.translation.x = camera.map_view.x;
+                transform.translation.y = camera.map_view.y;
+            }
+            (false, Some((world, scale))) => {
+                camera.map_view = transform.translation.truncate();
+                camera.world_view = None;
+                *transform = world;
+                projection.scale = scale;
+            }
+            _ => {}
+        }
+    }
+}
+
+pub(crate) fn set_zoom(mut query: Query<(&UNavCamera, &mut OrthographicProjection)>) {
+    for (camera, mut projection) in query.iter_mut() {
+        projection.scale = camera.zoom;
+    }
+}
+
+/// Converts a cursor position (window pixels, y down) into world coordinates
+/// for a camera at `translation` with the given `zoom`.
+fn cursor_to_world(window: &Window, cursor: Vec2, translation: Vec2, zoom: f32) -> Vec2 {
+    let from_center = cursor - window.size() / 2.0;
+    translation + Vec2::new(from_center.x, -from_center.y) * zoom
+}
+
+/// Returns the camera translation that keeps `anchor` at the same place on
+/// screen when zooming from `old_zoom` to `new_zoom`.
+pub fn zoom_towards(translation: Vec2, anchor: Vec2, old_zoom: f32, new_zoom: f32) -> Vec2 {
+    anchor - (anchor - translation) * (new_zoom / old_zoom)
+}
+
+// zoom on mouse scroll towards the cursor, clamped to MIN_ZOOM..MAX_ZOOM;
+// scrolling up zooms out, as it always has
+pub(crate) fn change_zoom(
+    q_window: Query<&Window, With<PrimaryWindow>>,
+    mut query: Query<(&mut UNavCamera, &mut Transform)>,
+    mut scroll: EventReader<MouseWheel>,
+) {
+    let Ok(window) = q_window.get_single() else {
+        return;
+    };
+
+    for event in scroll.read() {
+        let step = match event.unit {
+            MouseScrollUnit::Line => event.y * LINE_ZOOM_STEP,
+            MouseScrollUnit::Pixel => event.y * PIXEL_ZOOM_STEP,
+        };
+
+        for (mut camera, mut transform) in query.iter_mut() {
+            let old_zoom = camera.zoom;
+            let new_zoom = (old_zoom * (1.0 + step)).clamp(MIN_ZOOM, MAX_ZOOM);
+            if new_zoom == old_zoom {
+                continue;
+            }
+
+            if let Some(cursor) = window.cursor_position() {
+                let translation = transform.translation.truncate();
+                let anchor = cursor_to_world(window, cursor, translation, old_zoom);
+                let translation = zoom_towards(translation, anchor, old_zoom, new_zoom);
+                transform.translation.x = translation.x;
+                transform.translation.y = translation.y;
+            }
+
+            camera.zoom = new_zoom;
+        }
+    }
+}
+
+// pan the map while the right or middle mouse button is held
+pub(crate) fn drag_pan(
+    buttons: Res<ButtonInput<MouseButton>>,
+    mut motion: EventReader<MouseMotion>,
+    mut query: Query<(&UNavCamera, &mut Transform)>,
+) {
+    let delta: Vec2 = motion.read().map(|event| event.delta).sum();
+    if delta == Vec2::ZERO || !buttons.any_pressed([MouseButton::Right, MouseButton::Middle]) {
+        return;
+    }
+
+    for (camera, mut transform) in query.iter_mut() {
+        transform.translation.x -= delta.x * camera.zoom;
+        transform.translation.y += delta.y * camera.zoom;
+    }
+}
+
+pub(crate) fn keyboard_pan(
+    time: Res<Time>,
+    input: Res<ButtonInput<KeyCode>>,
+    mut query: Query<(&UNavCamera, &mut Transform)>,
+) {
+    let mut direction = Vec2::ZERO;
+    if input.any_pressed([KeyCode::KeyW, KeyCode::ArrowUp]) {
+        direction.y += 1.0;
+    }
+    if input.any_pressed([KeyCode::KeyS, KeyCode::ArrowDown]) {
+        direction.y -= 1.0;
+    }
+    if input.any_pressed([KeyCode::KeyA, KeyCode::ArrowLeft]) {
+        direction.x -= 1.0;
+    }
+    if input.any_pressed([KeyCode::KeyD, KeyCode::ArrowRight]) {
+        direction.x += 1.0;
+    }
+
+    if direction == Vec2::ZERO {
+        return;
+    }
+
+    let step = direction.normalize() * KEYBOARD_PAN_SPEED * time.delta_secs();
+    for (camera, mut transform) in query.iter_mut() {
+        transform.translation.x += step.x * camera.zoom;
+        transform.translation.y += step.y * camera.zoom;
+    }
+}
+
+pub(crate) fn camera_command_input(
+    input: Res<ButtonInput<KeyCode>>,
+    mut commands: EventWriter<UNavCameraCommand>,
+) {
+    if input.just_pressed(KeyCode::KeyC) {
+        commands.send(UNavCameraCommand::CenterOnCurrentSystem);
+    }
+    if input.just_pressed(KeyCode::KeyF) {
+        commands.send(UNavCameraCommand::FitMap);
+    }
+}
+
+pub(crate) fn on_camera_command(
+    mut commands: EventReader<UNavCameraCommand>,
+    unav: Res<UNav>,
+    current_system: Res<CurrentSystem>,
+    q_window: Query<&Window, With<PrimaryWindow>>,
+    mut query: Query<(&mut UNavCamera, &mut Transform)>,
+) {
+    for command in commands.read() {
+        match command {
+            UNavCameraCommand::CenterOnCurrentSystem => {
+                let Some(system) = unav.get_system(&current_system.0) else {
+                    continue;
+                };
+                for (_, mut transform) in query.iter_mut() {
+                    transform.translation.x = system.position.x as f32;
+                    transform.translation.y = system.position.y as f32;
+                }
+            }
+            UNavCameraCommand::FitMap => {
+                let Ok(window) = q_window.get_single() else {
+                    continue;
+                };
+                let Some((center, zoom)) = fit_map(&unav, window.size()) else {
+                    continue;
+                };
+                for (mut camera, mut transform) in query.iter_mut() {
+                    camera.zoom = zoom;
+                    transform.translation.x = center.x;
+                    transform.translation.y = center.y;
+                }
+            }
+        }
+    }
+}
+
+/// Computes the camera center and zoom that frame every system of the map
+/// inside a viewport of `viewport` logical pixels.
+pub fn fit_map(unav: &UNav, viewport: Vec2) -> Option<(Vec2, f32)> {
+    let mut positions = unav
+        .systems
+        .values()
+        .map(|system| Vec2::new(system.position.x as f32, system.position.y as f32));
+    let first = positions.next()?;
+    let (min, max) = positions.fold((first, first), |(min, max), p| (min.min(p), max.max(p)));
+
+    let extent = (max - min).max(Vec2::ONE) * FIT_MARGIN;
+    let zoom = (extent / viewport.max(Vec2::ONE))
+        .max_element()
+        .clamp(MIN_ZOOM, MAX_ZOOM);
+
+    Some(((min + max) / 2.0, zoom))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn closing_the_map_restores_the_game_view() {
+        let mut app = App::new();
+        app.insert_resource(UNavToggle { active: false })
+            .add_systems(Update, swap_view);
+        let world = Transform::from_xyz(300.0, -40.0, 1.0);
+        let camera = app
+            .world_mut()
+            .spawn((
+                world,
+                OrthographicProjection::default_2d(),
+                UNavCamera {
+                    zoom: 0.5,
+                    map_view: Vec2::ZERO,
+                    world_view: None,
+                },
+            ))
+            .id();
+
+        app.world_mut().resource_mut::<UNavToggle>().active = true;
+        app.update();
+        assert_eq!(
+            app.world().get::<Transform>(camera).unwrap().translation.x,
+            0.0
+        );
+
+        // pan and zoom the map
+        app.world_mut()
+            .get_mut::<Transform>(camera)
+            .unwrap()
+            .translation
+            .x = 900.0;
+        app.world_mut()
+            .get_mut::<OrthographicProjection>(camera)
+            .unwrap()
+            .scale = 3.0;
+
+        app.world_mut().resource_mut::<UNavToggle>().active = false;
+        app.update();
+        assert_eq!(*app.world().get::<Transform>(camera).unwrap(), world);
+        assert_eq!(
+            app.world()
+                .get::<OrthographicProjection>(camera)
+                .unwrap()
+                .scale,
+            1.0
+        );
+
+        // the map reopens where it was left
+        app.world_mut().resource_mut::<UNavToggle>().active = true;
+        app.update();
+        assert_eq!(
+            app.world().get::<Transform>(camera).unwrap().translation.x,
+            900.0
+        );
+    }
+}
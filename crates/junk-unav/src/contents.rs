@@ -0,0 +1,427 @@
+use std::fmt::Display;
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    faction::Faction,
+    system::{SystemComponents, SystemProperties},
+};
+
+/// Mixed into `System::seed` so contents don't share a random stream with
+/// the system's id and properties.
+const CONTENTS_SEED_SALT: u64 = 0x636f_6e74_656e_7473;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum StarType {
+    WhiteDwarf,
+    RedDwarf,
+    YellowDwarf,
+    BlueGiant,
+    NeutronStar,
+}
+
+impl StarType {
+    /// Picks a star type for a system, hotter systems get hotter stars.
+    fn from_temperature(rng: &mut StdRng, temperature: f64) -> Self {
+        if rng.gen_bool(0.05) {
+            return StarType::NeutronStar;
+        }
+        match temperature {
+            t if t < 0.2 => StarType::WhiteDwarf,
+            t if t < 0.45 => StarType::RedDwarf,
+            t if t < 0.8 => StarType::YellowDwarf,
+            _ => StarType::BlueGiant,
+        }
+    }
+}
+
+impl Display for StarType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            StarType::WhiteDwarf => "White Dwarf",
+            StarType::RedDwarf => "Red Dwarf",
+            StarType::YellowDwarf => "Yellow Dwarf",
+            StarType::BlueGiant => "Blue Giant",
+            StarType::NeutronStar => "Neutron Star",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Biome {
+    Ice,
+    Rock,
+    Gas,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ResourceKind {
+    Iron,
+    Nickel,
+    Ice,
+    Platinum,
+    Helium3,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ResourceYield {
+    pub resource: ResourceKind,
+    pub amount: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Planet {
+    pub name: String,
+    pub biome: Biome,
+    /// Orbit radius, in arbitrary in-system units.
+    pub orbit: f32,
+    /// Radius relative to the smallest possible planet.
+    pub size: f32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AsteroidBelt {
+    pub orbit: f32,
+    /// 0..1, how packed the belt is.
+    pub density: f32,
+    pub yields: Vec<ResourceYield>,
+}
+
+impl AsteroidBelt {
+    /// Takes up to `amount` of a resource out of the belt, returning how much
+    /// was actually mined.
+    pub fn mine(&mut self, resource: ResourceKind, amount: u32) -> u32 {
+        let Some(deposit) = self.yields.iter_mut().find(|y| y.resource == resource) else {
+            return 0;
+        };
+        let mined = amount.min(deposit.amount);
+        deposit.amount -= mined;
+        mined
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum StationService {
+    Trading,
+    Repairs,
+    Upgrades,
+    Quests,
+    Crew,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Station {
+    pub name: String,
+    pub faction: Faction,
+    /// Who built the station, it falls back to them when nobody holds the
+    /// system.
+    pub founder: Faction,
+    pub services: Vec<StationService>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LootKind {
+    Credits,
+    Resource(ResourceKind),
+    Part,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LootEntry {
+    pub kind: LootKind,
+    /// Relative chance of this entry being rolled.
+    pub weight: u32,
+    pub min: u32,
+    pub max: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WreckSite {
+    pub name: String,
+    pub loot: Vec<LootEntry>,
+}
+
+impl WreckSite {
+    /// Rolls one entry from the loot table, returning what and how much.
+    pub fn roll(&self, rng: &mut impl Rng) -> Option<(LootKind, u32)> {
+        let entry = self.loot.choose_weighted(rng, |entry| entry.weight).ok()?;
+        Some((entry.kind, rng.gen_range(entry.min..=entry.max)))
+    }
+}
+
+/// Everything that can be found inside a system, derived from its seed and
+/// `SystemProperties::type`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SystemContents {
+    pub star: StarType,
+    pub planets: Vec<Planet>,
+    pub asteroid_belts: Vec<AsteroidBelt>,
+    pub stations: Vec<Station>,
+    pub wrecks: Vec<WreckSite>,
+}
+
+impl SystemContents {
+    /// Updates the names of planets, stations and wrecks after the system
+    /// itself was renamed.
+    pub fn rename(&mut self, system_name: &str) {
+        for (i, planet) in self.planets.iter_mut().enumerate() {
+            planet.name = Self::planet_name(system_name, i);
+        }
+        for (i, station) in self.stations.iter_mut().enumerate() {
+            station.name = Self::station_name(system_name, i);
+        }
+        for (i, wreck) in self.wrecks.iter_mut().enumerate() {
+            wreck.name = Self::wreck_name(system_name, i);
+        }
+    }
+
+    // planets are lettered from b, as is tradition
+    fn planet_name(system_name: &str, index: usize) -> String {
+        format!("{} {}", system_name, (b'b' + index as u8) as char)
+    }
+
+    fn station_name(system_name: &str, index: usize) -> String {
+        format!("{} Station {}", system_name, index + 1)
+    }
+
+    fn wreck_name(system_name: &str, index: usize) -> String {
+        format!("{} Wreck {}", system_name, index + 1)
+    }
+
+    pub fn generate(seed: u64, properties: &SystemProperties) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed ^ CONTENTS_SEED_SALT);
+        let name = &properties.name.name;
+        let temperature = properties.temperature;
+        let has = |component: SystemComponents| properties.r#type.contains(&component);
+
+        let star = StarType::from_temperature(&mut rng, temperature);
+
+        let planets = if has(SystemComponents::Planet) {
+            let count = rng.gen_range(1..=4);
+            (0..count)
+                .map(|i| Planet {
+                    name: Self::planet_name(name, i),
+                    biome: Self::biome(&mut rng, temperature, i),
+                    orbit: 100.0 + i as f32 * rng.gen_range(80.0..160.0),
+                    size: rng.gen_range(1.0..4.0),
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let mut asteroid_belts = if has(SystemComponents::Asteroid) {
+            let count = rng.gen_range(1..=3);
+            (0..count)
+                .map(|_| AsteroidBelt {
+                    orbit: rng.gen_range(150.0..800.0),
+                    density: rng.gen_range(0.1..1.0),
+                    yields: Self::yields(&mut rng, temperature),
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let stations = if has(SystemComponents::Station) {
+            let count = rng.gen_range(1..=2);
+            (0..count)
+                .map(|i| {
+                    let faction = *[Faction::Traders, Faction::Authorities]
+                        .choose(&mut rng)
+                        .unwrap();
+                    Station {
+                        name: Self::station_name(name, i),
+                        faction,
+                        founder: faction,
+                        services: Self::services(&mut rng),
+                    }
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let mut wrecks = if has(SystemComponents::Wreckage) {
+            let count = rng.gen_range(1..=3);
+            (0..count)
+                .map(|i| WreckSite {
+                    name: Self::wreck_name(name, i),
+                    loot: Self::loot_table(&mut rng),
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        // dangerous systems are worth the trip
+        let reward = properties.reward_multiplier();
+        let scale = |amount: u32| (amount as f64 * reward).round() as u32;
+        for belt in asteroid_belts.iter_mut() {
+            for resource in belt.yields.iter_mut() {
+                resource.amount = scale(resource.amount);
+            }
+        }
+        for wreck in wrecks.iter_mut() {
+            for entry in wreck.loot.iter_mut() {
+                if entry.kind != LootKind::Part {
+                    entry.min = scale(entry.min);
+                    entry.max = scale(entry.max);
+                }
+            }
+        }
+
+        Self {
+            star,
+            planets,
+            asteroid_belts,
+            stations,
+            wrecks,
+        }
+    }
+
+    // inner planets lean towards rock, outer ones towards gas and ice
+    fn biome(rng: &mut StdRng, temperature: f64, index: usize) -> Biome {
+        let ice = (1.0 - temperature) * 3.0 + index as f64;
+        let rock = temperature * 3.0 + 1.0;
+        let gas = 1.0 + index as f64;
+        let roll = rng.gen_range(0.0..ice + rock + gas);
+        if roll < ice {
+            Biome::Ice
+        } else if roll < ice + rock {
+            Biome::Rock
+        } else {
+            Biome::Gas
+        }
+    }
+
+    fn yields(rng: &mut StdRng, temperature: f64) -> Vec<ResourceYield> {
+        let mut yields = vec![ResourceYield {
+            resource: ResourceKind::Iron,
+            amount: rng.gen_range(20..100),
+        }];
+        if rng.gen_bool(0.5) {
+            yields.push(ResourceYield {
+                resource: ResourceKind::Nickel,
+                amount: rng.gen_range(10..60),
+            });
+        }
+        if rng.gen_bool(1.0 - temperature) {
+            yields.push(ResourceYield {
+                resource: ResourceKind::Ice,
+                amount: rng.gen_range(10..80),
+            });
+        }
+        if rng.gen_bool(0.1) {
+            yields.push(ResourceYield {
+                resource: ResourceKind::Platinum,
+                amount: rng.gen_range(1..10),
+            });
+        }
+        yields
+    }
+
+    fn services(rng: &mut StdRng) -> Vec<StationService> {
+        let mut services = vec![StationService::Trading];
+        for service in [
+            StationService::Repairs,
+            StationService::Upgrades,
+            StationService::Quests,
+            StationService::Crew,
+        ] {
+            if rng.gen_bool(0.5) {
+                services.push(service);
+            }
+        }
+        services
+    }
+
+    fn loot_table(rng: &mut StdRng) -> Vec<LootEntry> {
+        vec![
+            LootEntry {
+                kind: LootKind::Credits,
+                weight: rng.gen_range(3..6),
+                min: 10,
+                max: 200,
+            },
+            LootEntry {
+                kind: LootKind::Resource(ResourceKind::Iron),
+                weight: rng.gen_range(2..5),
+                min: 5,
+                max: 40,
+            },
+            LootEntry {
+                kind: LootKind::Resource(ResourceKind::Helium3),
+                weight: rng.gen_range(0..2),
+                min: 1,
+                max: 10,
+            },
+            LootEntry {
+                kind: LootKind::Part,
+                weight: rng.gen_range(1..3),
+                min: 1,
+                max: 1,
+            },
+        ]
+    }
+}
+
+impl Display for SystemContents {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Star: {}", self.star)?;
+        if !self.planets.is_empty() {
+            let biomes = self
+                .planets
+                .iter()
+                .map(|planet| format!("{:?}", planet.biome))
+                .collect::<Vec<_>>()
+                .join(", ");
+            write!(f, "\nPlanets: {}", biomes)?;
+        }
+        if !self.asteroid_belts.is_empty() {
+            write!(f, "\nAsteroid belts: {}", self.asteroid_belts.len())?;
+        }
+        for station in &self.stations {
+            write!(f, "\n{} ({:?})", station.name, station.faction)?;
+        }
+        if !self.wrecks.is_empty() {
+            write!(f, "\nWreck sites: {}", self.wrecks.len())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{system::SystemComponents, UNav};
+
+    #[test]
+    fn contents_match_components() {
+        let unav = UNav::generate(19940131);
+        for system in unav.systems.values() {
+            let has = |component| system.properties.r#type.contains(&component);
+            let contents = &system.contents;
+            assert_eq!(has(SystemComponents::Planet), !contents.planets.is_empty());
+            assert_eq!(
+                has(SystemComponents::Asteroid),
+                !contents.asteroid_belts.is_empty()
+            );
+            assert_eq!(
+                has(SystemComponents::Station),
+                !contents.stations.is_empty()
+            );
+            assert_eq!(has(SystemComponents::Wreckage), !contents.wrecks.is_empty());
+        }
+    }
+
+    #[test]
+    fn contents_are_deterministic() {
+        let a = UNav::generate(42);
+        let b = UNav::generate(42);
+        for (id, system) in a.systems.iter() {
+            assert_eq!(system.contents, b.systems[id].contents);
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use std::collections::VecDeque;
+
+use bevy_math::I64Vec2;
+
+use crate::unav::CONNECTION_DISTANCE;
+
+/// Danger given to systems that can't be reached from the start at all.
+const UNREACHABLE_DANGER: f64 = 1.0;
+
+fn manhattan(a: I64Vec2, b: I64Vec2) -> usize {
+    ((a.x - b.x).abs() + (a.y - b.y).abs()) as usize
+}
+
+/// Ordering key used to pick the starting system: closest to the origin,
+/// ties broken by position so the choice is stable.
+pub(crate) fn centrality_key(position: I64Vec2) -> (i64, i64, i64) {
+    (position.x.abs() + position.y.abs(), position.x, position.y)
+}
+
+/// Danger for every position, 0.0 at the most central one and 1.0 at the
+/// ones the most jumps away from it.
+pub fn danger_levels(positions: &[I64Vec2]) -> Vec<f64> {
+    let Some(start) = (0..positions.len()).min_by_key(|&i| centrality_key(positions[i])) else {
+        return Vec::new();
+    };
+
+    let mut jumps: Vec<Option<usize>> = vec![None; positions.len()];
+    jumps[start] = Some(0);
+
+    let mut queue = VecDeque::from([start]);
+    while let Some(current) = queue.pop_front() {
+        let next = jumps[current].unwrap() + 1;
+        for (i, position) in positions.iter().enumerate() {
+            if jumps[i].is_none() && manhattan(positions[current], *position) <= CONNECTION_DISTANCE
+            {
+                jumps[i] = Some(next);
+                queue.push_back(i);
+            }
+        }
+    }
+
+    let max_jumps = jumps.iter().flatten().copied().max().unwrap_or(0).max(1);
+    jumps
+        .into_iter()
+        .map(|jumps| match jumps {
+            Some(jumps) => jumps as f64 / max_jumps as f64,
+            None => UNREACHABLE_DANGER,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{SystemComponents, UNav};
+
+    #[test]
+    fn start_is_safe() {
+        let unav = UNav::generate(19940131);
+        assert_eq!(unav.get_most_central_system().properties.danger, 0.0);
+    }
+
+    #[test]
+    fn pirates_gather_far_out() {
+        let mut near = 0;
+        let mut far = 0;
+        for seed in 0..20 {
+            let unav = UNav::generate(seed);
+            for system in unav.systems.values() {
+                if !system
+                    .properties
+                    .r#type
+                    .contains(&SystemComponents::Pirates)
+                {
+                    continue;
+                }
+                if system.properties.danger < 0.5 {
+                    near += 1;
+                } else {
+                    far += 1;
+                }
+            }
+        }
+        assert!(far > near, "{} pirate systems near, {} far", near, far);
+    }
+}
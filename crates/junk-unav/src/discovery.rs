@@ -0,0 +1,129 @@
+use bevy_ecs::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{SystemId, UNav};
+
+/// How much the player knows about a system. Knowledge only ever increases.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub enum Knowledge {
+    /// Not on the map at all.
+    #[default]
+    Unknown,
+    /// Position is known, nothing else.
+    Detected,
+    /// Name, temperature and components have been revealed by a scan.
+    Scanned,
+    /// The player has been there.
+    Visited,
+}
+
+impl Knowledge {
+    pub fn is_known(&self) -> bool {
+        *self > Knowledge::Unknown
+    }
+
+    pub fn is_scanned(&self) -> bool {
+        *self >= Knowledge::Scanned
+    }
+}
+
+/// Sensor reach of the player's ship, in UNav distance units.
+/// Systems within `scan` are fully revealed, systems within `detection` only
+/// show up as a position. Thick nebulae stop systems from being scanned.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct SensorRange {
+    pub detection: usize,
+    pub scan: usize,
+}
+
+impl Default for SensorRange {
+    fn default() -> Self {
+        Self {
+            detection: 70,
+            scan: 35,
+        }
+    }
+}
+
+impl UNav {
+    /// Reveal the map around `from` as if the player had just arrived there.
+    pub fn discover(&mut self, from: &SystemId, sensors: &SensorRange) {
+        let Some(origin) = self.systems.get(from).cloned() else {
+            return;
+        };
+
+        for system in self.systems.values_mut() {
+            let distance = origin.distance_to(system);
+            let knowledge = if system.id == *from {
+                Knowledge::Visited
+            } else if distance <= sensors.scan && !system.properties.hazards.blocks_sensors() {
+                Knowledge::Scanned
+            } else if distance <= sensors.detection {
+                Knowledge::Detected
+            } else {
+                Knowledge::Unknown
+            };
+            system.knowledge = system.knowledge.max(knowledge);
+        }
+    }
+}
+
+#[cfg(feature = "plugin")]
+pub(crate) fn update_discovery(
+    mut unav: ResMut<UNav>,
+    current_system: Res<crate::CurrentSystem>,
+    sensors: Res<SensorRange>,
+) {
+    unav.discover(&current_system.0, &sensors);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn discover_reveals_around_current_system() {
+        let mut unav = UNav::generate(19940131);
+        let start = unav.get_most_central_system().clone();
+        let sensors = SensorRange::default();
+
+        unav.discover(&start.id, &sensors);
+
+        for system in unav.systems.values() {
+            let distance = start.distance_to(system);
+            match system.knowledge {
+                Knowledge::Visited => assert_eq!(system.id, start.id),
+                Knowledge::Scanned => {
+                    assert!(distance <= sensors.scan);
+                    assert!(!system.properties.hazards.blocks_sensors());
+                }
+                Knowledge::Detected => assert!(distance <= sensors.detection),
+                Knowledge::Unknown => assert!(distance > sensors.detection),
+            }
+        }
+    }
+
+    #[test]
+    fn knowledge_is_never_lost() {
+        let mut unav = UNav::generate(19940131);
+        let start = unav.get_most_central_system().id.clone();
+        unav.discover(&start, &SensorRange::default());
+
+        let far = unav
+            .systems
+            .values()
+            .find(|system| system.knowledge == Knowledge::Unknown)
+            .unwrap()
+            .id
+            .clone();
+        unav.discover(&far, &SensorRange::default());
+
+        assert_eq!(
+            unav.get_system(&start).unwrap().knowledge,
+            Knowledge::Visited
+        );
+        assert_eq!(unav.get_system(&far).unwrap().knowledge, Knowledge::Visited);
+    }
+}
@@ -0,0 +1,200 @@
+use std::fmt::Write;
+
+use bevy_color::{Color, Srgba};
+use serde::Serialize;
+
+use crate::{
+    shape::{GalaxyShape, GalaxySize},
+    unav::CONNECTION_DISTANCE,
+    Connection, System, SystemId, UNav,
+};
+
+/// Space left around the systems in SVG renders, in UNav units.
+const SVG_MARGIN: i64 = 20;
+
+/// Radius of a system in SVG renders, in UNav units.
+const SVG_SYSTEM_RADIUS: f64 = 2.5;
+
+const UNCLAIMED_COLOR: Color = Color::srgb(0.6, 0.6, 0.6);
+
+/// Shape of the JSON export.
+#[derive(Serialize)]
+struct GalaxyExport<'a> {
+    seed: u32,
+    shape: GalaxyShape,
+    size: GalaxySize,
+    systems: Vec<&'a System>,
+    connections: Vec<Connection>,
+}
+
+fn hex(color: Color) -> String {
+    Srgba::from(color).to_hex()
+}
+
+fn owner_color(system: &System) -> String {
+    hex(system.owner.map_or(UNCLAIMED_COLOR, |owner| owner.color()))
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+impl UNav {
+    /// Systems sorted by id so exports are stable between runs.
+    fn sorted_systems(&self) -> Vec<&System> {
+        let mut systems: Vec<&System> = self.systems.values().collect();
+        systems.sort_by(|a, b| a.id.0.cmp(&b.id.0));
+        systems
+    }
+
+    /// Every connection once, rather than once in each direction.
+    fn undirected_connections(&self) -> Vec<Connection> {
+        let mut connections: Vec<Connection> = self
+            .connections(CONNECTION_DISTANCE)
+            .connections
+            .into_iter()
+            .filter(|connection| connection.from.0 < connection.to.0)
+            .collect();
+        connections.sort_by(|a, b| (&a.from.0, &a.to.0).cmp(&(&b.from.0, &b.to.0)));
+        connections
+    }
+
+    /// Graphviz DOT with systems pinned to their map positions, so
+    /// `neato -n` draws the galaxy as it appears in game.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("graph unav {\n    node [shape=point];\n");
+        for system in self.sorted_systems() {
+            writeln!(
+                dot,
+                "    \"{}\" [label=\"{}\", pos=\"{},{}!\", color=\"{}\", danger={:.2}];",
+                system.id.0,
+                system.properties.name,
+                system.position.x,
+                system.position.y,
+                owner_color(system),
+                system.properties.danger,
+            )
+            .unwrap();
+        }
+        for connection in self.undirected_connections() {
+            writeln!(
+                dot,
+                "    \"{}\" -- \"{}\" [len={}];",
+                connection.from.0, connection.to.0, connection.distance
+            )
+            .unwrap();
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Systems with all their properties and contents, plus connections.
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(&GalaxyExport {
+            seed: self.seed,
+            shape: self.shape,
+            size: self.size,
+            systems: self.sorted_systems(),
+            connections: self.undirected_connections(),
+        })
+    }
+
+    /// A standalone render of the map: connections, systems coloured by
+    /// owner and their names.
+    pub fn to_svg(&self) -> String {
+        let systems = self.sorted_systems();
+        let min_x = systems.iter().map(|s| s.position.x).min().unwrap_or(0) - SVG_MARGIN;
+        let max_x = systems.iter().map(|s| s.position.x).max().unwrap_or(0) + SVG_MARGIN;
+        let min_y = systems.iter().map(|s| s.position.y).min().unwrap_or(0) - SVG_MARGIN;
+        let max_y = systems.iter().map(|s| s.position.y).max().unwrap_or(0) + SVG_MARGIN;
+
+        // the UNav's y axis points up, SVG's points down
+        let x = |system: &System| system.position.x;
+        let y = |system: &System| -system.position.y;
+        let position = |id: &SystemId| self.get_system(id).map(|system| (x(system), y(system)));
+
+        let mut svg = String::new();
+        writeln!(
+            svg,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\">",
+            min_x,
+            -max_y,
+            max_x - min_x,
+            max_y - min_y
+        )
+        .unwrap();
+        writeln!(
+            svg,
+            "  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"black\"/>",
+            min_x,
+            -max_y,
+            max_x - min_x,
+            max_y - min_y
+        )
+        .unwrap();
+
+        svg.push_str("  <g stroke=\"#404040\" stroke-width=\"0.5\">\n");
+        for connection in self.undirected_connections() {
+            let (Some((x1, y1)), Some((x2, y2))) =
+                (position(&connection.from), position(&connection.to))
+            else {
+                continue;
+            };
+            writeln!(
+                svg,
+                "    <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\"/>",
+                x1, y1, x2, y2
+            )
+            .unwrap();
+        }
+        svg.push_str("  </g>\n");
+
+        svg.push_str("  <g font-family=\"monospace\" font-size=\"4\" fill=\"white\">\n");
+        for system in systems {
+            writeln!(
+                svg,
+                "    <circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\"><title>{}</title></circle>",
+                x(system),
+                y(system),
+                SVG_SYSTEM_RADIUS,
+                owner_color(system),
+                escape_xml(&system.id.0),
+            )
+            .unwrap();
+            writeln!(
+                svg,
+                "    <text x=\"{}\" y=\"{}\">{}</text>",
+                x(system) as f64 + SVG_SYSTEM_RADIUS * 1.5,
+                y(system),
+                escape_xml(&system.properties.name.name),
+            )
+            .unwrap();
+        }
+        svg.push_str("  </g>\n</svg>\n");
+        svg
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::UNav;
+
+    #[test]
+    fn exports_every_system() {
+        let unav = UNav::generate(19940131);
+        let dot = unav.to_dot();
+        let svg = unav.to_svg();
+        let json: serde_json::Value = serde_json::from_str(&unav.to_json().unwrap()).unwrap();
+
+        assert_eq!(
+            json["systems"].as_array().unwrap().len(),
+            unav.systems.len()
+        );
+        for id in unav.systems.keys() {
+            assert!(dot.contains(&id.0));
+            assert!(svg.contains(&id.0));
+        }
+    }
+}
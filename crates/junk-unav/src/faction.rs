@@ -0,0 +1,205 @@
+use std::collections::{HashMap, VecDeque};
+
+use bevy_color::Color;
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::{unav::CONNECTION_DISTANCE, Hazards, SystemId, UNav};
+
+/// Mixed into the UNav seed so territory generation has its own random stream.
+const TERRITORY_SEED_SALT: u64 = 0x7465_7272_6974_6f72;
+
+/// How many home systems each faction gets.
+const HOMES_PER_FACTION: usize = 2;
+
+/// How many jumps a faction's territory reaches out from each home system.
+const TERRITORY_JUMPS: usize = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Faction {
+    Traders,
+    Pirates,
+    Authorities,
+}
+
+impl Faction {
+    pub const ALL: [Faction; 3] = [Faction::Traders, Faction::Pirates, Faction::Authorities];
+
+    /// Tint used for this faction's territory on the UNav.
+    pub fn color(&self) -> Color {
+        match self {
+            Faction::Traders => Color::srgb(0.9, 0.75, 0.2),
+            Faction::Pirates => Color::srgb(0.85, 0.15, 0.15),
+            Faction::Authorities => Color::srgb(0.2, 0.5, 0.95),
+        }
+    }
+}
+
+/// Relative chance of meeting ships of each faction in a system.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EncounterOdds {
+    pub traders: f32,
+    pub pirates: f32,
+    pub authorities: f32,
+}
+
+impl EncounterOdds {
+    pub fn for_owner(owner: Option<Faction>) -> Self {
+        match owner {
+            Some(Faction::Traders) => Self {
+                traders: 0.6,
+                pirates: 0.15,
+                authorities: 0.25,
+            },
+            Some(Faction::Pirates) => Self {
+                traders: 0.1,
+                pirates: 0.8,
+                authorities: 0.1,
+            },
+            Some(Faction::Authorities) => Self {
+                traders: 0.35,
+                pirates: 0.05,
+                authorities: 0.6,
+            },
+            None => Self {
+                traders: 0.3,
+                pirates: 0.4,
+                authorities: 0.3,
+            },
+        }
+    }
+
+    /// Shifts the odds with a system's hazards: pirates lurk in nebulae,
+    /// traders stay clear of radiation and patrols of ion storms.
+    pub fn with_hazards(self, hazards: &Hazards) -> Self {
+        Self {
+            traders: self.traders * (1.0 - 0.8 * hazards.radiation as f32),
+            pirates: self.pirates * (1.0 + hazards.nebula as f32),
+            authorities: self.authorities * (1.0 - 0.5 * hazards.ion_storm as f32),
+        }
+    }
+
+    pub fn get(&self, faction: Faction) -> f32 {
+        match faction {
+            Faction::Traders => self.traders,
+            Faction::Pirates => self.pirates,
+            Faction::Authorities => self.authorities,
+        }
+    }
+
+    /// Picks whose ship turns up for a uniform `roll` in 0.0..1.0.
+    pub fn pick(&self, roll: f32) -> Faction {
+        let total: f32 = Faction::ALL.iter().map(|faction| self.get(*faction)).sum();
+        let mut roll = roll * total;
+        for faction in Faction::ALL {
+            roll -= self.get(faction);
+            if roll < 0.0 {
+                return faction;
+            }
+        }
+        Faction::ALL[Faction::ALL.len() - 1]
+    }
+}
+
+impl UNav {
+    /// Picks faction home systems and grows territories out from them over
+    /// the connection graph. Contested systems go to whoever gets there first.
+    pub fn assign_territories(&mut self, seed: u64) {
+        let mut rng = StdRng::seed_from_u64(seed ^ TERRITORY_SEED_SALT);
+
+        let mut ids: Vec<SystemId> = self.systems.keys().cloned().collect();
+        ids.sort_by(|a, b| a.0.cmp(&b.0));
+        ids.shuffle(&mut rng);
+
+        let adjacency = self.connections(CONNECTION_DISTANCE).adjacency();
+
+        // homes alternate between factions so nobody gets a head start
+        let mut queue = VecDeque::new();
+        for (i, id) in ids
+            .iter()
+            .take(HOMES_PER_FACTION * Faction::ALL.len())
+            .enumerate()
+        {
+            let faction = Faction::ALL[i % Faction::ALL.len()];
+            queue.push_back((id.clone(), faction, 0));
+        }
+
+        let mut owners: HashMap<SystemId, Faction> = HashMap::new();
+        while let Some((id, faction, jumps)) = queue.pop_front() {
+            if owners.contains_key(&id) {
+                continue;
+            }
+            owners.insert(id.clone(), faction);
+
+            if jumps >= TERRITORY_JUMPS {
+                continue;
+            }
+            let Some(neighbours) = adjacency.get(&id) else {
+                continue;
+            };
+            for neighbour in neighbours {
+                if !owners.contains_key(neighbour) {
+                    queue.push_back((neighbour.clone(), faction, jumps + 1));
+                }
+            }
+        }
+
+        for system in self.systems.values_mut() {
+            let owner = owners.get(&system.id).copied();
+            system.set_owner(owner);
+        }
+    }
+
+    pub fn owner(&self, id: &SystemId) -> Option<Faction> {
+        self.get_system(id).and_then(|system| system.owner)
+    }
+
+    pub fn territory(&self, faction: Faction) -> Vec<SystemId> {
+        self.systems
+            .values()
+            .filter(|system| system.owner == Some(faction))
+            .map(|system| system.id.clone())
+            .collect()
+    }
+
+    pub fn encounter_odds(&self, id: &SystemId) -> EncounterOdds {
+        let odds = EncounterOdds::for_owner(self.owner(id));
+        match self.get_system(id) {
+            Some(system) => odds.with_hazards(&system.properties.hazards),
+            None => odds,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_faction_holds_territory() {
+        let unav = UNav::generate(19940131);
+        for faction in Faction::ALL {
+            assert!(unav.territory(faction).len() >= HOMES_PER_FACTION);
+        }
+    }
+
+    #[test]
+    fn picks_follow_the_odds() {
+        let odds = EncounterOdds::for_owner(Some(Faction::Pirates));
+        let pirates = (0..100)
+            .filter(|i| odds.pick((*i as f32 + 0.5) / 100.0) == Faction::Pirates)
+            .count();
+        assert_eq!(pirates, 80);
+        assert_eq!(odds.pick(0.0), Faction::Traders);
+        assert_eq!(odds.pick(0.999), Faction::Authorities);
+    }
+
+    #[test]
+    fn territories_are_deterministic() {
+        let a = UNav::generate(7);
+        let b = UNav::generate(7);
+        for (id, system) in a.systems.iter() {
+            assert_eq!(system.owner, b.systems[id].owner);
+        }
+    }
+}
@@ -0,0 +1,370 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    discovery::SensorRange, faction::Faction, stats::jumps_from, unav::CONNECTION_DISTANCE,
+    CurrentSystem, Jumped, SystemComponents, SystemId, UNav, UNavEntity, UNavToggle,
+};
+
+/// Mixed into the UNav seed so fleets have their own random stream.
+const FLEET_SEED_SALT: u64 = 0x666c_6565_7473;
+
+/// Mixed into the fleet seed for rolling ambushes on jumps.
+const AMBUSH_SEED_SALT: u64 = 0x616d_6275_7368;
+
+/// One fleet of each kind for this many systems.
+const SYSTEMS_PER_FLEET: usize = 12;
+
+/// How far a fleet looks for its next destination, in jumps.
+const FLEET_RANGE_JUMPS: usize = 5;
+
+const FLEET_ICON_RADIUS: f32 = 3.0;
+
+/// Galactic time, one turn passes per jump the player makes.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GalacticClock {
+    pub turn: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FleetKind {
+    /// Hauls goods between stations.
+    Trader,
+    /// Hunts for traders around stations and trade territory.
+    Raider,
+    /// Keeps the peace in Authority space.
+    Patrol,
+}
+
+impl FleetKind {
+    pub const ALL: [FleetKind; 3] = [FleetKind::Trader, FleetKind::Raider, FleetKind::Patrol];
+
+    pub fn faction(&self) -> Faction {
+        match self {
+            FleetKind::Trader => Faction::Traders,
+            FleetKind::Raider => Faction::Pirates,
+            FleetKind::Patrol => Faction::Authorities,
+        }
+    }
+
+    /// Whether this kind of fleet wants to go to `id`.
+    fn wants(&self, unav: &UNav, id: &SystemId) -> bool {
+        let Some(system) = unav.get_system(id) else {
+            return false;
+        };
+        let has = |component| system.properties.r#type.contains(&component);
+        match self {
+            FleetKind::Trader => has(SystemComponents::Station),
+            FleetKind::Raider => {
+                has(SystemComponents::Station) || system.owner == Some(Faction::Traders)
+            }
+            FleetKind::Patrol => system.owner == Some(Faction::Authorities),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Fleet {
+    pub id: u32,
+    pub kind: FleetKind,
+    pub location: SystemId,
+    pub destination: SystemId,
+}
+
+/// Every NPC fleet in the galaxy.
+#[derive(Resource, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Fleets {
+    pub fleets: Vec<Fleet>,
+    seed: u64,
+}
+
+impl Fleets {
+    /// Places fleets in systems they'd want to be in.
+    pub fn spawn(unav: &UNav) -> Self {
+        let seed = unav.seed as u64 ^ FLEET_SEED_SALT;
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut ids: Vec<&SystemId> = unav.systems.keys().collect();
+        ids.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let per_kind = (unav.systems.len() / SYSTEMS_PER_FLEET).max(1);
+        let mut fleets = Vec::new();
+        for kind in FleetKind::ALL {
+            let homes: Vec<&&SystemId> = ids.iter().filter(|id| kind.wants(unav, id)).collect();
+            for _ in 0..per_kind {
+                let Some(home) = homes.choose(&mut rng) else {
+                    break;
+                };
+                fleets.push(Fleet {
+                    id: fleets.len() as u32,
+                    kind,
+                    location: (**home).clone(),
+                    destination: (**home).clone(),
+                });
+            }
+        }
+        Self { fleets, seed }
+    }
+
+    /// Moves every fleet one jump towards its destination, picking a new one
+    /// for fleets that have arrived.
+    pub fn advance(&mut self, unav: &UNav, turn: u64) {
+        let adjacency = unav.connections(CONNECTION_DISTANCE).adjacency();
+        for fleet in self.fleets.iter_mut() {
+            let mut rng = StdRng::seed_from_u64(self.seed ^ turn ^ ((fleet.id as u64) << 32));
+            if fleet.location == fleet.destination {
+                fleet.destination = next_destination(unav, &adjacency, fleet, &mut rng);
+            }
+            if let Some(next) = step_towards(&adjacency, &fleet.location, &fleet.destination) {
+                fleet.location = next;
+            }
+        }
+    }
+
+    /// Whoever interrupts the jump from `from` to `to`, if anyone does. Jumps
+    /// into dangerous systems and through nebulae are interrupted more often,
+    /// by whichever faction is about at the destination. Ambushers aren't
+    /// tracked on the map, their ids follow the tracked fleets'.
+    pub fn ambush(&self, unav: &UNav, from: &SystemId, to: &SystemId, turn: u64) -> Option<Fleet> {
+        let cost = unav.travel_cost(from, to)?;
+        let mut rng = StdRng::seed_from_u64(self.seed ^ turn ^ AMBUSH_SEED_SALT);
+        if !rng.gen_bool(cost.encounter_chance) {
+            return None;
+        }
+        let odds = unav.encounter_odds(to);
+        let kind = *FleetKind::ALL
+            .choose_weighted(&mut rng, |kind| odds.get(kind.faction()))
+            .ok()?;
+        Some(Fleet {
+            id: self.fleets.len() as u32 + turn as u32,
+            kind,
+            location: to.clone(),
+            destination: to.clone(),
+        })
+    }
+
+    pub fn at<'a>(&'a self, id: &'a SystemId) -> impl Iterator<Item = &'a Fleet> + 'a {
+        self.fleets
+            .iter()
+            .filter(move |fleet| fleet.location == *id)
+    }
+}
+
+fn next_destination(
+    unav: &UNav,
+    adjacency: &HashMap<SystemId, Vec<SystemId>>,
+    fleet: &Fleet,
+    rng: &mut StdRng,
+) -> SystemId {
+    let mut options: Vec<SystemId> = jumps_from(&fleet.location, adjacency)
+        .into_iter()
+        .filter(|(id, jumps)| (1..=FLEET_RANGE_JUMPS).contains(jumps) && fleet.kind.wants(unav, id))
+        .map(|(id, _)| id)
+        .collect();
+    options.sort_by(|a, b| a.0.cmp(&b.0));
+
+    // nowhere they want to go, wander to a neighbour instead
+    if options.is_empty() {
+        options = adjacency.get(&fleet.location).cloned().unwrap_or_default();
+        options.sort_by(|a, b| a.0.cmp(&b.0));
+    }
+    options
+        .choose(rng)
+        .cloned()
+        .unwrap_or_else(|| fleet.location.clone())
+}
+
+/// The neighbour of `from` that is fewest jumps from `to`.
+fn step_towards(
+    adjacency: &HashMap<SystemId, Vec<SystemId>>,
+    from: &SystemId,
+    to: &SystemId,
+) -> Option<SystemId> {
+    if from == to {
+        return None;
+    }
+    let jumps = jumps_from(to, adjacency);
+    adjacency
+        .get(from)?
+        .iter()
+        .filter_map(|id| Some((id, *jumps.get(id)?)))
+        .min_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0 .0.cmp(&b.0 .0)))
+        .map(|(id, _)| id.clone())
+}
+
+/// The player shares a system with a fleet, or was ambushed on the way in.
+#[derive(Event, Clone, Debug, PartialEq)]
+pub struct FleetEncounter(pub Fleet);
+
+#[derive(Component)]
+pub(crate) struct FleetMarker;
+
+/// Advances time and the fleets when the player jumps, then checks for
+/// encounters at the destination and along the way.
+pub(crate) fn advance_clock(
+    unav: Res<UNav>,
+    mut jumps: EventReader<Jumped>,
+    mut clock: ResMut<GalacticClock>,
+    mut fleets: ResMut<Fleets>,
+    mut encounters: EventWriter<FleetEncounter>,
+) {
+    for Jumped { from, to, .. } in jumps.read() {
+        clock.turn += 1;
+        fleets.advance(&unav, clock.turn);
+        let ambush = fleets.ambush(&unav, from, to, clock.turn);
+        encounters.send_batch(fleets.at(to).cloned().chain(ambush).map(FleetEncounter));
+    }
+}
+
+/// Draws fleets within detection range of the player.
+pub(crate) fn draw_fleets(
+    mut commands: Commands,
+    unav: Res<UNav>,
+    fleets: Res<Fleets>,
+    current_system: Res<CurrentSystem>,
+    sensors: Res<SensorRange>,
+    unav_toggle: Res<UNavToggle>,
+    markers: Query<Entity, With<FleetMarker>>,
+) {
+    for marker in markers.iter() {
+        commands.entity(marker).despawn();
+    }
+    let Some(player) = unav.get_system(&current_system.0) else {
+        return;
+    };
+    let visibility = if unav_toggle.active {
+        Visibility::Visible
+    } else {
+        Visibility::Hidden
+    };
+
+    let triangle = shapes::RegularPolygon {
+        sides: 3,
+        feature: shapes::RegularPolygonFeature::Radius(FLEET_ICON_RADIUS),
+        ..default()
+    };
+    // fleets sharing a system are fanned out around it
+    let mut per_system: HashMap<&SystemId, usize> = HashMap::new();
+    for fleet in fleets.fleets.iter() {
+        let Some(system) = unav.get_system(&fleet.location) else {
+            continue;
+        };
+        if player.distance_to(system) > sensors.detection {
+            continue;
+        }
+        let slot = per_system.entry(&fleet.location).or_default();
+        let offset = Vec2::from_angle(*slot as f32 * 1.2) * FLEET_ICON_RADIUS * 2.5;
+        *slot += 1;
+
+        commands.spawn((
+            ShapeBundle {
+                path: GeometryBuilder::build_as(&triangle),
+                transform: Transform::from_xyz(
+                    system.position.x as f32 + offset.x,
+                    system.position.y as f32 + offset.y,
+                    3.0,
+                ),
+                visibility,
+                ..default()
+            },
+            Fill::color(fleet.kind.faction().color()),
+            FleetMarker,
+            UNavEntity,
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fleets_move_along_connections() {
+        let unav = UNav::generate(19940131);
+        let connections = unav.connections(CONNECTION_DISTANCE);
+        let mut fleets = Fleets::spawn(&unav);
+        assert!(FleetKind::ALL
+            .iter()
+            .all(|kind| fleets.fleets.iter().any(|fleet| fleet.kind == *kind)));
+
+        let mut moved = 0;
+        for turn in 1..20 {
+            let before = fleets.clone();
+            fleets.advance(&unav, turn);
+            for (old, new) in before.fleets.iter().zip(fleets.fleets.iter()) {
+                if old.location != new.location {
+                    moved += 1;
+                    assert!(connections.can_navigate_to(&old.location, &new.location));
+                }
+            }
+        }
+        assert!(moved > 0);
+    }
+
+    #[test]
+    fn fleets_are_deterministic() {
+        let unav = UNav::generate(3);
+        let mut a = Fleets::spawn(&unav);
+        let mut b = Fleets::spawn(&unav);
+        for turn in 1..10 {
+            a.advance(&unav, turn);
+            b.advance(&unav, turn);
+        }
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn some_jumps_are_ambushed() {
+        let unav = UNav::generate(19940131);
+        let fleets = Fleets::spawn(&unav);
+        let connections = unav.connections(CONNECTION_DISTANCE);
+        let ambushes: Vec<Fleet> = connections
+            .connections()
+            .iter()
+            .enumerate()
+            .filter_map(|(turn, c)| fleets.ambush(&unav, &c.from, &c.to, turn as u64))
+            .collect();
+        assert!(!ambushes.is_empty());
+        assert!(ambushes.len() < connections.connections().len());
+        assert!(ambushes
+            .iter()
+            .all(|fleet| fleet.id as usize >= fleets.fleets.len()));
+    }
+
+    #[test]
+    fn jumping_advances_the_clock_and_fleets() {
+        let unav = UNav::generate(19940131);
+        let start = unav.get_most_central_system().id.clone();
+        let neighbour = unav
+            .connections(CONNECTION_DISTANCE)
+            .get_navigatable_systems(&start)[0]
+            .clone();
+        let fleets = Fleets::spawn(&unav);
+        let mut moved = fleets.clone();
+        moved.advance(&unav, 1);
+        assert_ne!(fleets, moved);
+
+        let mut app = App::new();
+        app.insert_resource(unav)
+            .insert_resource(CurrentSystem(start))
+            .insert_resource(fleets)
+            .init_resource::<GalacticClock>()
+            .init_resource::<crate::Fuel>()
+            .add_event::<crate::JumpTo>()
+            .add_event::<Jumped>()
+            .add_event::<FleetEncounter>()
+            .add_systems(Update, (crate::jump::jump, advance_clock).chain());
+
+        // time stands still until the player jumps
+        app.update();
+        assert_eq!(app.world().resource::<GalacticClock>().turn, 0);
+
+        app.world_mut().send_event(crate::JumpTo(neighbour));
+        app.update();
+        assert_eq!(app.world().resource::<GalacticClock>().turn, 1);
+        assert_eq!(*app.world().resource::<Fleets>(), moved);
+    }
+}
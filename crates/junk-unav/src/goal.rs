@@ -0,0 +1,433 @@
+use std::{collections::HashMap, fmt::Display};
+
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    stats::jumps_from, unav::CONNECTION_DISTANCE, CurrentSystem, SystemId, UNav, UNavEntity,
+    UNavToggle,
+};
+
+/// Mixed into the UNav seed so goals have their own random stream.
+const GOAL_SEED_SALT: u64 = 0x676f_616c;
+
+/// Extra jumps allowed on top of the shortest route for the first objective.
+const BASE_SPARE_JUMPS: usize = 8;
+
+/// Endless mode takes one spare jump away per objective, down to this many.
+const MIN_SPARE_JUMPS: usize = 2;
+
+const MARKER_RADIUS: f32 = 8.0;
+
+/// What the player picked to do this run.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GoalKind {
+    /// Carry a message to a system halfway across the map.
+    #[default]
+    DeliverMessage,
+    /// Find the source of a signal out at the edge of the map.
+    InvestigateSignal,
+    /// Keep taking on objectives, each further out and with less slack.
+    Endless,
+}
+
+impl GoalKind {
+    pub const ALL: [GoalKind; 3] = [
+        GoalKind::DeliverMessage,
+        GoalKind::InvestigateSignal,
+        GoalKind::Endless,
+    ];
+
+    /// Range of jumps from the start a target may be, as fractions of the
+    /// farthest reachable system.
+    fn target_range(&self, level: u32) -> (f64, f64) {
+        match self {
+            GoalKind::DeliverMessage => (0.5, 0.75),
+            GoalKind::InvestigateSignal => (0.8, 1.0),
+            GoalKind::Endless => {
+                let low = (0.2 + 0.1 * level as f64).min(0.8);
+                (low, (low + 0.2).min(1.0))
+            }
+        }
+    }
+}
+
+/// A single system to reach within a number of jumps.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Objective {
+    pub target: SystemId,
+    /// Starts at 0 and counts up in endless mode.
+    pub level: u32,
+    pub jumps_taken: usize,
+    pub jumps_allowed: usize,
+}
+
+impl Objective {
+    pub fn jumps_left(&self) -> usize {
+        self.jumps_allowed.saturating_sub(self.jumps_taken)
+    }
+}
+
+/// The run's goal and how far along the player is.
+#[derive(Resource, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Goal {
+    pub kind: GoalKind,
+    /// `None` once the goal has been won or lost.
+    pub objective: Option<Objective>,
+    pub completed: u32,
+    /// Where the player was last time progress was checked.
+    last_system: SystemId,
+}
+
+#[derive(Event, Clone, Debug, PartialEq)]
+pub enum GoalEvent {
+    ObjectiveCompleted { target: SystemId, level: u32 },
+    Won,
+    Lost,
+}
+
+impl UNav {
+    /// Picks a system for an objective starting at `from`, somewhere
+    /// between `min` and `max` of the way to the farthest reachable system.
+    pub fn pick_goal_target(
+        &self,
+        from: &SystemId,
+        (min, max): (f64, f64),
+        rng: &mut StdRng,
+    ) -> Option<(SystemId, usize)> {
+        let adjacency = self.connections(CONNECTION_DISTANCE).adjacency();
+        let jumps = jumps_from(from, &adjacency);
+        let reach = jumps.values().copied().max().filter(|reach| *reach > 0)?;
+
+        let mut candidates: Vec<(&SystemId, usize)> = jumps
+            .iter()
+            .filter(|(_, &jumps)| {
+                let fraction = jumps as f64 / reach as f64;
+                jumps > 0 && fraction >= min && fraction <= max
+            })
+            .map(|(id, &jumps)| (id, jumps))
+            .collect();
+        candidates.sort_by(|a, b| a.0 .0.cmp(&b.0 .0));
+
+        // small maps may have nothing in range, settle for the farthest
+        let (id, jumps) = match candidates.choose(rng) {
+            Some(candidate) => *candidate,
+            None => farthest(&jumps)?,
+        };
+        Some((id.clone(), jumps))
+    }
+}
+
+fn farthest(jumps: &HashMap<SystemId, usize>) -> Option<(&SystemId, usize)> {
+    jumps
+        .iter()
+        .map(|(id, jumps)| (id, *jumps))
+        .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0 .0.cmp(&a.0 .0)))
+}
+
+/// Why a goal couldn't be set up.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GoalError {
+    /// No lane leaves the start, so there's nowhere to send the player.
+    Unreachable(SystemId),
+}
+
+impl Display for GoalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GoalError::Unreachable(start) => {
+                write!(f, "no system can be reached from {:?}", start)
+            }
+        }
+    }
+}
+
+impl std::error::Error for GoalError {}
+
+impl Goal {
+    pub fn new(kind: GoalKind, unav: &UNav, start: &SystemId) -> Result<Self, GoalError> {
+        let mut goal = Self {
+            kind,
+            objective: None,
+            completed: 0,
+            last_system: start.clone(),
+        };
+        goal.objective = goal.next_objective(unav, start, 0);
+        if goal.objective.is_none() {
+            return Err(GoalError::Unreachable(start.clone()));
+        }
+        Ok(goal)
+    }
+
+    fn next_objective(&self, unav: &UNav, from: &SystemId, level: u32) -> Option<Objective> {
+        let mut rng = StdRng::seed_from_u64(unav.seed as u64 ^ GOAL_SEED_SALT ^ level as u64);
+        let range = self.kind.target_range(level);
+        let (target, jumps) = unav.pick_goal_target(from, range, &mut rng)?;
+        let spare = BASE_SPARE_JUMPS
+            .saturating_sub(level as usize)
+            .max(MIN_SPARE_JUMPS);
+        Some(Objective {
+            target,
+            level,
+            jumps_taken: 0,
+            jumps_allowed: jumps + spare,
+        })
+    }
+
+    pub fn target(&self) -> Option<&SystemId> {
+        self.objective.as_ref().map(|objective| &objective.target)
+    }
+
+    /// Records the player arriving at `current`, returning what happened.
+    pub fn arrive(&mut self, unav: &UNav, current: &SystemId) -> Vec<GoalEvent> {
+        if *current == self.last_system {
+            return Vec::new();
+        }
+        self.last_system = current.clone();
+        let Some(objective) = self.objective.as_mut() else {
+            return Vec::new();
+        };
+        objective.jumps_taken += 1;
+
+        let mut events = Vec::new();
+        if objective.target == *current {
+            let level = objective.level;
+            events.push(GoalEvent::ObjectiveCompleted {
+                target: current.clone(),
+                level,
+            });
+            self.completed += 1;
+            self.objective = match self.kind {
+                GoalKind::Endless => self.next_objective(unav, current, level + 1),
+                _ => None,
+            };
+            if self.objective.is_none() {
+                events.push(GoalEvent::Won);
+            }
+        } else if objective.jumps_left() == 0 {
+            self.objective = None;
+            events.push(GoalEvent::Lost);
+        }
+        events
+    }
+}
+
+impl Display for Goal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self.kind {
+            GoalKind::DeliverMessage => "Deliver the message",
+            GoalKind::InvestigateSignal => "Investigate the signal",
+            GoalKind::Endless => "Endless",
+        };
+        write!(f, "{}", name)?;
+        if self.kind == GoalKind::Endless {
+            write!(f, " ({} completed)", self.completed)?;
+        }
+        match &self.objective {
+            Some(objective) => write!(f, "\nJumps left: {}", objective.jumps_left()),
+            None => write!(f, "\nOver"),
+        }
+    }
+}
+
+#[derive(Component)]
+pub(crate) struct GoalMarker;
+
+#[derive(Component)]
+pub(crate) struct GoalText;
+
+pub(crate) fn spawn_goal_ui(mut commands: Commands) {
+    commands
+        .spawn(Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(20.0),
+            left: Val::Px(20.0),
+            padding: UiRect::all(Val::Px(10.0)),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn((Text::new(""), GoalText, UNavEntity));
+        });
+}
+
+pub(crate) fn track_goal(
+    unav: Res<UNav>,
+    current_system: Res<CurrentSystem>,
+    mut goal: ResMut<Goal>,
+    mut events: EventWriter<GoalEvent>,
+) {
+    let happened = goal.arrive(&unav, &current_system.0);
+    events.send_batch(happened);
+}
+
+/// Redraws the target marker and progress text whenever the goal changes.
+pub(crate) fn draw_goal(
+    mut commands: Commands,
+    unav: Res<UNav>,
+    goal: Res<Goal>,
+    unav_toggle: Res<UNavToggle>,
+    markers: Query<Entity, With<GoalMarker>>,
+    mut text: Query<&mut Text, With<GoalText>>,
+) {
+    for marker in markers.iter() {
+        commands.entity(marker).despawn();
+    }
+    for mut text in text.iter_mut() {
+        **text = goal.to_string();
+    }
+
+    // signals are found by triangulation, see `signal.rs`
+    if goal.kind == GoalKind::InvestigateSignal {
+        return;
+    }
+    let Some(target) = goal.target().and_then(|target| unav.get_system(target)) else {
+        return;
+    };
+    let ring = shapes::Circle {
+        radius: MARKER_RADIUS,
+        center: Vec2::ZERO,
+    };
+    commands.spawn((
+        ShapeBundle {
+            path: GeometryBuilder::build_as(&ring),
+            transform: Transform::from_xyz(target.position.x as f32, target.position.y as f32, 2.0),
+            visibility: if unav_toggle.active {
+                Visibility::Visible
+            } else {
+                Visibility::Hidden
+            },
+            ..default()
+        },
+        Stroke::new(Color::srgb(0.2, 1.0, 0.4), 1.5),
+        GoalMarker,
+        UNavEntity,
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{jump, Fuel, JumpTo, Jumped};
+    use bevy::math::I64Vec2;
+
+    /// Shortest route from `from` to `to` over the connection graph.
+    fn route(unav: &UNav, from: &SystemId, to: &SystemId) -> Vec<SystemId> {
+        let adjacency = unav.connections(CONNECTION_DISTANCE).adjacency();
+        let jumps = jumps_from(to, &adjacency);
+        let mut route = Vec::new();
+        let mut current = from.clone();
+        while current != *to {
+            current = adjacency[&current]
+                .iter()
+                .min_by_key(|id| jumps.get(*id).copied().unwrap_or(usize::MAX))
+                .unwrap()
+                .clone();
+            route.push(current.clone());
+        }
+        route
+    }
+
+    #[test]
+    fn delivering_wins() {
+        let unav = UNav::generate(19940131);
+        let start = unav.get_most_central_system().id.clone();
+        let mut goal = Goal::new(GoalKind::DeliverMessage, &unav, &start).unwrap();
+        let target = goal.target().unwrap().clone();
+
+        let mut events = Vec::new();
+        for id in route(&unav, &start, &target) {
+            events.extend(goal.arrive(&unav, &id));
+        }
+        assert_eq!(events.last(), Some(&GoalEvent::Won));
+        assert!(goal.objective.is_none());
+    }
+
+    #[test]
+    fn wandering_loses() {
+        let unav = UNav::generate(19940131);
+        let start = unav.get_most_central_system().id.clone();
+        let mut goal = Goal::new(GoalKind::InvestigateSignal, &unav, &start).unwrap();
+        let neighbour = unav
+            .connections(CONNECTION_DISTANCE)
+            .get_navigatable_systems(&start)[0]
+            .clone();
+
+        let mut events = Vec::new();
+        for jump in 0..1000 {
+            let id = if jump % 2 == 0 { &neighbour } else { &start };
+            events.extend(goal.arrive(&unav, id));
+            if goal.objective.is_none() {
+                break;
+            }
+        }
+        assert_eq!(events.last(), Some(&GoalEvent::Lost));
+    }
+
+    #[test]
+    fn endless_escalates() {
+        let unav = UNav::generate(19940131);
+        let start = unav.get_most_central_system().id.clone();
+        let mut goal = Goal::new(GoalKind::Endless, &unav, &start).unwrap();
+
+        let mut current = start;
+        let mut allowed = Vec::new();
+        for _ in 0..3 {
+            let objective = goal.objective.clone().unwrap();
+            allowed.push(objective.jumps_allowed - route(&unav, &current, &objective.target).len());
+            for id in route(&unav, &current, &objective.target) {
+                goal.arrive(&unav, &id);
+            }
+            current = objective.target;
+        }
+        assert_eq!(goal.completed, 3);
+        assert!(allowed.windows(2).all(|pair| pair[1] < pair[0]));
+    }
+
+    #[test]
+    fn isolated_start_has_no_goal() {
+        let mut unav = UNav::generate(19940131);
+        // drag a system out beyond every lane
+        let isolated = unav.get_most_central_system().id.clone();
+        unav.systems.get_mut(&isolated).unwrap().position = I64Vec2::splat(100_000);
+        for kind in GoalKind::ALL {
+            assert_eq!(
+                Goal::new(kind, &unav, &isolated),
+                Err(GoalError::Unreachable(isolated.clone()))
+            );
+        }
+    }
+
+    #[test]
+    fn jumping_counts_towards_the_goal() {
+        let unav = UNav::generate(19940131);
+        let start = unav.get_most_central_system().id.clone();
+        let goal = Goal::new(GoalKind::DeliverMessage, &unav, &start).unwrap();
+        let first_hop = route(&unav, &start, goal.target().unwrap())[0].clone();
+
+        let mut app = App::new();
+        app.insert_resource(unav)
+            .insert_resource(goal)
+            .insert_resource(CurrentSystem(start))
+            .init_resource::<Fuel>()
+            .add_event::<JumpTo>()
+            .add_event::<Jumped>()
+            .add_event::<GoalEvent>()
+            .add_systems(
+                Update,
+                (
+                    jump::jump,
+                    track_goal
+                        .after(jump::jump)
+                        .run_if(resource_changed::<CurrentSystem>),
+                ),
+            );
+        app.update();
+
+        app.world_mut().send_event(JumpTo(first_hop));
+        app.update();
+        let objective = app.world().resource::<Goal>().objective.clone().unwrap();
+        assert_eq!(objective.jumps_taken, 1);
+    }
+}
@@ -0,0 +1,269 @@
+use std::fmt::Display;
+
+use bevy_math::{DVec2, I64Vec2};
+use noise::{Fbm, MultiFractal, NoiseFn, Perlin};
+use serde::{Deserialize, Serialize};
+
+use crate::{unav::CONNECTION_DISTANCE, SystemId, UNav};
+
+/// Mixed into the UNav seed so each hazard gets its own noise.
+const ION_STORM_SEED_SALT: u32 = 0x696f_6e73;
+const NEBULA_SEED_SALT: u32 = 0x6e65_6275;
+const RADIATION_SEED_SALT: u32 = 0x7261_6469;
+
+/// Size of a hazard "blob" in UNav units, roughly.
+const HAZARD_SCALE: f64 = 160.0;
+
+/// Noise values between these fade from nothing to a full hazard.
+const HAZARD_EDGE_LOW: f64 = 0.2;
+const HAZARD_EDGE_HIGH: f64 = 0.5;
+
+/// Nebula intensity above which sensors can't scan a system from afar.
+const NEBULA_SENSOR_BLOCK: f64 = 0.5;
+
+/// Number of points sampled along a jump to estimate its hazards.
+const ROUTE_SAMPLES: usize = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum HazardKind {
+    /// Raise fuel cost, ships get thrown around.
+    IonStorm,
+    /// Block long range sensors, hide pirates.
+    Nebula,
+    /// Damage hulls, keep traders away.
+    Radiation,
+}
+
+impl HazardKind {
+    pub const ALL: [HazardKind; 3] = [
+        HazardKind::IonStorm,
+        HazardKind::Nebula,
+        HazardKind::Radiation,
+    ];
+}
+
+/// Intensity of every hazard at a point, each 0.0 to 1.0.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Hazards {
+    pub ion_storm: f64,
+    pub nebula: f64,
+    pub radiation: f64,
+}
+
+impl Hazards {
+    pub fn get(&self, kind: HazardKind) -> f64 {
+        match kind {
+            HazardKind::IonStorm => self.ion_storm,
+            HazardKind::Nebula => self.nebula,
+            HazardKind::Radiation => self.radiation,
+        }
+    }
+
+    pub fn is_clear(&self) -> bool {
+        HazardKind::ALL.iter().all(|kind| self.get(*kind) <= 0.0)
+    }
+
+    /// Whether a nebula is thick enough to block sensors here.
+    pub fn blocks_sensors(&self) -> bool {
+        self.nebula > NEBULA_SENSOR_BLOCK
+    }
+
+    /// Multiplier applied to fuel spent travelling through these hazards.
+    pub fn fuel_multiplier(&self) -> f64 {
+        1.0 + self.ion_storm * 1.5 + self.nebula * 0.5
+    }
+
+    /// Extra chance of an encounter: pirates lurk in nebulae, everyone
+    /// avoids radiation.
+    pub fn encounter_modifier(&self) -> f64 {
+        1.0 + self.nebula * 0.6 + self.ion_storm * 0.2 - self.radiation * 0.5
+    }
+}
+
+impl Display for Hazards {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names = HazardKind::ALL
+            .iter()
+            .filter(|kind| self.get(**kind) > 0.0)
+            .map(|kind| match kind {
+                HazardKind::IonStorm => "Ion storm",
+                HazardKind::Nebula => "Nebula",
+                HazardKind::Radiation => "Radiation",
+            })
+            .collect::<Vec<_>>();
+        if names.is_empty() {
+            write!(f, "None")
+        } else {
+            write!(f, "{}", names.join(", "))
+        }
+    }
+}
+
+/// Noise fields describing hazardous regions of the galaxy. Serialized as
+/// just its seed, the noise is rebuilt on load.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(from = "u32", into = "u32")]
+pub struct HazardField {
+    seed: u32,
+    ion_storm: Fbm<Perlin>,
+    nebula: Fbm<Perlin>,
+    radiation: Fbm<Perlin>,
+}
+
+impl HazardField {
+    pub fn new(seed: u32) -> Self {
+        let field = |salt: u32| Fbm::<Perlin>::new(seed ^ salt).set_octaves(4);
+        Self {
+            seed,
+            ion_storm: field(ION_STORM_SEED_SALT),
+            nebula: field(NEBULA_SEED_SALT),
+            radiation: field(RADIATION_SEED_SALT),
+        }
+    }
+
+    pub fn intensity(&self, kind: HazardKind, point: DVec2) -> f64 {
+        let noise = match kind {
+            HazardKind::IonStorm => &self.ion_storm,
+            HazardKind::Nebula => &self.nebula,
+            HazardKind::Radiation => &self.radiation,
+        };
+        let p = point / HAZARD_SCALE;
+        let value = noise.get([p.x, p.y]);
+        let t = ((value - HAZARD_EDGE_LOW) / (HAZARD_EDGE_HIGH - HAZARD_EDGE_LOW)).clamp(0.0, 1.0);
+        t * t * (3.0 - 2.0 * t)
+    }
+
+    pub fn at(&self, point: DVec2) -> Hazards {
+        Hazards {
+            ion_storm: self.intensity(HazardKind::IonStorm, point),
+            nebula: self.intensity(HazardKind::Nebula, point),
+            radiation: self.intensity(HazardKind::Radiation, point),
+        }
+    }
+
+    /// Average hazards along a straight jump between two points.
+    pub fn along(&self, from: I64Vec2, to: I64Vec2) -> Hazards {
+        let from = from.as_dvec2();
+        let to = to.as_dvec2();
+        let mut total = Hazards::default();
+        for i in 0..ROUTE_SAMPLES {
+            let t = (i as f64 + 0.5) / ROUTE_SAMPLES as f64;
+            let hazards = self.at(from.lerp(to, t));
+            total.ion_storm += hazards.ion_storm;
+            total.nebula += hazards.nebula;
+            total.radiation += hazards.radiation;
+        }
+        let n = ROUTE_SAMPLES as f64;
+        Hazards {
+            ion_storm: total.ion_storm / n,
+            nebula: total.nebula / n,
+            radiation: total.radiation / n,
+        }
+    }
+}
+
+impl From<u32> for HazardField {
+    fn from(seed: u32) -> Self {
+        HazardField::new(seed)
+    }
+}
+
+impl From<HazardField> for u32 {
+    fn from(field: HazardField) -> Self {
+        field.seed
+    }
+}
+
+/// What a single jump between two systems costs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TravelCost {
+    pub fuel: f64,
+    /// Chance, 0.0 to 1.0, of being interrupted by an encounter.
+    pub encounter_chance: f64,
+    pub hazards: Hazards,
+}
+
+impl UNav {
+    pub fn hazards_at(&self, point: I64Vec2) -> Hazards {
+        self.hazards.at(point.as_dvec2())
+    }
+
+    /// What jumping from `from` to `to` costs, `None` unless a lane joins
+    /// them.
+    pub fn travel_cost(&self, from: &SystemId, to: &SystemId) -> Option<TravelCost> {
+        let from = self.get_system(from)?;
+        let to = self.get_system(to)?;
+        if from.id == to.id || from.distance_to(to) > CONNECTION_DISTANCE {
+            return None;
+        }
+        let hazards = self.hazards.along(from.position, to.position);
+
+        let fuel = from.distance_to(to) as f64 * hazards.fuel_multiplier();
+        let base_chance = 0.1 + 0.4 * to.properties.danger;
+        let encounter_chance = (base_chance * hazards.encounter_modifier()).clamp(0.0, 1.0);
+
+        Some(TravelCost {
+            fuel,
+            encounter_chance,
+            hazards,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EncounterOdds;
+
+    #[test]
+    fn hazards_change_across_the_map() {
+        let unav = UNav::generate(19940131);
+        let hazardous = unav
+            .systems
+            .values()
+            .filter(|system| !system.properties.hazards.is_clear())
+            .count();
+        assert!(hazardous > 0);
+        assert!(hazardous < unav.systems.len());
+    }
+
+    #[test]
+    fn storms_make_jumps_expensive() {
+        let clear = Hazards::default();
+        let storm = Hazards {
+            ion_storm: 1.0,
+            ..Hazards::default()
+        };
+        assert!(storm.fuel_multiplier() > clear.fuel_multiplier());
+    }
+
+    #[test]
+    fn jumps_cost_more_through_hazards() {
+        let unav = UNav::generate(19940131);
+        let connections = unav.connections(CONNECTION_DISTANCE);
+        for connection in connections.connections() {
+            let cost = unav.travel_cost(&connection.from, &connection.to).unwrap();
+            assert!(cost.fuel >= connection.distance as f64);
+            assert!((0.0..=1.0).contains(&cost.encounter_chance));
+        }
+
+        let start = unav.get_most_central_system();
+        let far = unav
+            .systems
+            .values()
+            .find(|system| start.distance_to(system) > CONNECTION_DISTANCE)
+            .unwrap();
+        assert_eq!(unav.travel_cost(&start.id, &far.id), None);
+    }
+
+    #[test]
+    fn nebulae_draw_pirates() {
+        let odds = EncounterOdds::for_owner(None);
+        let nebula = Hazards {
+            nebula: 1.0,
+            ..Hazards::default()
+        };
+        assert!(odds.with_hazards(&nebula).pirates > odds.pirates);
+        assert_eq!(odds.with_hazards(&Hazards::default()), odds);
+    }
+}
@@ -0,0 +1,181 @@
+use std::fmt::Display;
+
+use bevy::prelude::*;
+
+use crate::{
+    unav::CONNECTION_DISTANCE, CurrentSystem, HoveredSystem, SystemComponents, SystemId,
+    TravelCost, UNav,
+};
+
+/// Fuel the ship starts with and can hold at most.
+const FUEL_CAPACITY: f64 = 400.0;
+
+/// Fuel in the player's tanks. Jumps burn it, stations top it up.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct Fuel {
+    pub amount: f64,
+    pub capacity: f64,
+}
+
+impl Default for Fuel {
+    fn default() -> Self {
+        Self {
+            amount: FUEL_CAPACITY,
+            capacity: FUEL_CAPACITY,
+        }
+    }
+}
+
+impl Display for Fuel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Fuel: {:.0}/{:.0}", self.amount, self.capacity)
+    }
+}
+
+/// Asks to jump from the current system to a neighbouring one.
+#[derive(Event, Clone, Debug, PartialEq)]
+pub struct JumpTo(pub SystemId);
+
+/// The player jumped along a lane and paid for it.
+#[derive(Event, Clone, Debug, PartialEq)]
+pub struct Jumped {
+    pub from: SystemId,
+    pub to: SystemId,
+    pub cost: TravelCost,
+}
+
+impl UNav {
+    /// What jumping from `from` to `to` costs, `None` if no lane joins them.
+    pub fn jump_cost(&self, from: &SystemId, to: &SystemId) -> Option<TravelCost> {
+        let neighbours = self
+            .connections(CONNECTION_DISTANCE)
+            .get_navigatable_systems(from);
+        if !neighbours.contains(to) {
+            return None;
+        }
+        self.travel_cost(from, to)
+    }
+}
+
+// clicking a system on the map jumps there
+pub(crate) fn click_to_jump(
+    buttons: Res<ButtonInput<MouseButton>>,
+    hovered: Query<&HoveredSystem>,
+    mut jumps: EventWriter<JumpTo>,
+) {
+    if !buttons.just_pressed(MouseButton::Left) {
+        return;
+    }
+    for HoveredSystem(id) in hovered.iter() {
+        jumps.send(JumpTo(id.clone()));
+    }
+}
+
+pub(crate) fn jump(
+    unav: Res<UNav>,
+    mut requests: EventReader<JumpTo>,
+    mut current_system: ResMut<CurrentSystem>,
+    mut fuel: ResMut<Fuel>,
+    mut jumped: EventWriter<Jumped>,
+) {
+    for JumpTo(to) in requests.read() {
+        let from = current_system.0.clone();
+        let Some(cost) = unav.jump_cost(&from, to) else {
+            warn!("No lane from {:?} to {:?}", from, to);
+            continue;
+        };
+        if cost.fuel > fuel.amount {
+            warn!(
+                "Jumping to {:?} takes {:.0} fuel, only {:.0} left",
+                to, cost.fuel, fuel.amount
+            );
+            continue;
+        }
+
+        fuel.amount -= cost.fuel;
+        // stations sell fuel, so arriving at one fills the tanks
+        if unav.get_system(to).is_some_and(|system| {
+            system
+                .properties
+                .r#type
+                .contains(&SystemComponents::Station)
+        }) {
+            fuel.amount = fuel.capacity;
+        }
+        current_system.0 = to.clone();
+        jumped.send(Jumped {
+            from,
+            to: to.clone(),
+            cost,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app(unav: UNav) -> App {
+        let start = unav.get_most_central_system().id.clone();
+        let mut app = App::new();
+        app.insert_resource(unav)
+            .insert_resource(CurrentSystem(start))
+            .init_resource::<Fuel>()
+            .add_event::<JumpTo>()
+            .add_event::<Jumped>()
+            .add_systems(Update, jump);
+        app
+    }
+
+    #[test]
+    fn jumps_follow_lanes_and_burn_fuel() {
+        let unav = UNav::generate(19940131);
+        let start = unav.get_most_central_system().id.clone();
+        let connections = unav.connections(CONNECTION_DISTANCE);
+        let neighbour = connections
+            .get_navigatable_systems(&start)
+            .into_iter()
+            .find(|id| {
+                !unav.systems[id]
+                    .properties
+                    .r#type
+                    .contains(&SystemComponents::Station)
+            })
+            .unwrap();
+        let far = unav
+            .systems
+            .keys()
+            .find(|id| **id != start && !connections.get_navigatable_systems(&start).contains(id))
+            .unwrap()
+            .clone();
+        let cost = unav.jump_cost(&start, &neighbour).unwrap();
+        let mut app = app(unav);
+
+        // not a neighbour, nothing happens
+        app.world_mut().send_event(JumpTo(far));
+        app.update();
+        assert_eq!(app.world().resource::<CurrentSystem>().0, start);
+
+        app.world_mut().send_event(JumpTo(neighbour.clone()));
+        app.update();
+        assert_eq!(app.world().resource::<CurrentSystem>().0, neighbour);
+        let fuel = app.world().resource::<Fuel>();
+        assert!((fuel.capacity - fuel.amount - cost.fuel).abs() < 1e-9);
+    }
+
+    #[test]
+    fn empty_tanks_stay_put() {
+        let unav = UNav::generate(19940131);
+        let start = unav.get_most_central_system().id.clone();
+        let neighbour = unav
+            .connections(CONNECTION_DISTANCE)
+            .get_navigatable_systems(&start)[0]
+            .clone();
+        let mut app = app(unav);
+        app.world_mut().resource_mut::<Fuel>().amount = 0.0;
+
+        app.world_mut().send_event(JumpTo(neighbour));
+        app.update();
+        assert_eq!(app.world().resource::<CurrentSystem>().0, start);
+    }
+}
@@ -0,0 +1,153 @@
+use std::collections::HashSet;
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::{faction::Faction, SystemId, UNav};
+
+/// Mixed into `System::seed` so names have their own random stream.
+const NAME_SEED_SALT: u64 = 0x6e61_6d65_7321;
+
+/// Rerolls before a numeral is tacked on to make a name unique.
+const MAX_NAME_ATTEMPTS: usize = 32;
+
+const NUMERALS: [&str; 9] = ["II", "III", "IV", "V", "VI", "VII", "VIII", "IX", "X"];
+
+/// Syllables and suffixes giving each faction's space its own flavour.
+struct NameStyle {
+    onsets: &'static [&'static str],
+    middles: &'static [&'static str],
+    codas: &'static [&'static str],
+    suffixes: &'static [&'static str],
+    /// Chance of a suffix being added.
+    suffix_chance: f64,
+}
+
+const TRADER_STYLE: NameStyle = NameStyle {
+    onsets: &[
+        "Vel", "Mar", "Sel", "Lun", "Al", "Cal", "Nor", "Ser", "Tal", "El",
+    ],
+    middles: &["o", "a", "i", "ora", "ene", "ia", "ari"],
+    codas: &["n", "s", "ra", "na", "lis", "via", "mar", "ssa"],
+    suffixes: &["Haven", "Market", "Crossing", "Landing"],
+    suffix_chance: 0.15,
+};
+
+const PIRATE_STYLE: NameStyle = NameStyle {
+    onsets: &[
+        "Kr", "Gr", "Vex", "Sk", "Dr", "Zar", "Rak", "Mor", "Thr", "Bl",
+    ],
+    middles: &["a", "o", "u", "ag", "ok", "ax", "ur"],
+    codas: &["k", "g", "x", "th", "z", "gor", "rak", "ash"],
+    suffixes: &["Reach", "Maw", "Hollow", "Drift"],
+    suffix_chance: 0.2,
+};
+
+const AUTHORITY_STYLE: NameStyle = NameStyle {
+    onsets: &[
+        "Aug", "Cor", "Prim", "Val", "Cent", "Imp", "Reg", "Cas", "Dom", "Vic",
+    ],
+    middles: &["u", "a", "e", "i", "ent", "er", "ul"],
+    codas: &["us", "um", "ia", "is", "or", "ant", "ium"],
+    suffixes: &["Prime", "Major", "Minor", "Secundus"],
+    suffix_chance: 0.25,
+};
+
+const UNCLAIMED_STYLE: NameStyle = NameStyle {
+    onsets: &["Ka", "Te", "Zy", "Ob", "Ith", "Ny", "Qu", "Ar", "Eph", "Ul"],
+    middles: &["a", "e", "o", "ae", "y", "il", "on"],
+    codas: &["n", "r", "th", "x", "l", "s", "on", "ek"],
+    suffixes: &["Deep", "Expanse", "Verge"],
+    suffix_chance: 0.1,
+};
+
+impl NameStyle {
+    fn for_owner(owner: Option<Faction>) -> &'static NameStyle {
+        match owner {
+            Some(Faction::Traders) => &TRADER_STYLE,
+            Some(Faction::Pirates) => &PIRATE_STYLE,
+            Some(Faction::Authorities) => &AUTHORITY_STYLE,
+            None => &UNCLAIMED_STYLE,
+        }
+    }
+
+    fn generate(&self, rng: &mut StdRng) -> String {
+        let mut name = rng_pick(rng, self.onsets).to_string();
+        for _ in 0..rng.gen_range(0..=2) {
+            name.push_str(rng_pick(rng, self.middles));
+        }
+        name.push_str(rng_pick(rng, self.codas));
+
+        if rng.gen_bool(self.suffix_chance) {
+            name = format!("{} {}", name, rng_pick(rng, self.suffixes));
+        }
+        name
+    }
+}
+
+fn rng_pick<'a>(rng: &mut StdRng, options: &[&'a str]) -> &'a str {
+    options.choose(rng).unwrap()
+}
+
+impl UNav {
+    /// Gives every system a pronounceable name in the style of the faction
+    /// holding it. Names are unique within the map.
+    pub fn assign_names(&mut self) {
+        let mut ids: Vec<SystemId> = self.systems.keys().cloned().collect();
+        ids.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut used = HashSet::new();
+        for id in ids {
+            let system = self.systems.get_mut(&id).unwrap();
+            let mut rng = StdRng::seed_from_u64(system.seed ^ NAME_SEED_SALT);
+            let style = NameStyle::for_owner(system.owner);
+
+            let mut name = style.generate(&mut rng);
+            for _ in 0..MAX_NAME_ATTEMPTS {
+                if !used.contains(&name) {
+                    break;
+                }
+                name = style.generate(&mut rng);
+            }
+            if used.contains(&name) {
+                let base = name.clone();
+                name = NUMERALS
+                    .iter()
+                    .map(|numeral| format!("{} {}", base, numeral))
+                    .chain((11..).map(|n| format!("{} {}", base, n)))
+                    .find(|candidate| !used.contains(candidate))
+                    .unwrap();
+            }
+
+            used.insert(name.clone());
+            system.properties.name.name = name;
+            system.contents.rename(&system.properties.name.name);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::UNav;
+
+    #[test]
+    fn names_are_unique() {
+        let unav = UNav::generate(19940131);
+        let names: HashSet<_> = unav
+            .systems
+            .values()
+            .map(|system| system.properties.name.name.clone())
+            .collect();
+        assert_eq!(names.len(), unav.systems.len());
+    }
+
+    #[test]
+    fn names_are_deterministic() {
+        let a = UNav::generate(3);
+        let b = UNav::generate(3);
+        for (id, system) in a.systems.iter() {
+            assert_eq!(system.properties.name, b.systems[id].properties.name);
+        }
+    }
+}
@@ -0,0 +1,574 @@
+use bevy::image::ImageSampler;
+use bevy::prelude::*;
+use bevy::render::{
+    render_asset::RenderAssetUsages,
+    render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+use bevy::window::PrimaryWindow;
+use bevy_prototype_lyon::prelude::*;
+
+use crate::{
+    camera::*,
+    discovery::{update_discovery, SensorRange},
+    fleet::{self, FleetEncounter, Fleets, GalacticClock},
+    goal::{self, Goal, GoalEvent, GoalKind},
+    jump::{self, Fuel, JumpTo, Jumped},
+    quest::{self, PiratesDefeated, QuestBoards, QuestCompleted, QuestLog},
+    signal::{self, Signal},
+    GalaxyShape, GalaxySize, HazardKind, System, SystemId, UNav, UNavSave, CONNECTION_DISTANCE,
+};
+
+pub struct UNavPlugin {
+    unav: UNav,
+    goal: GoalKind,
+}
+
+impl UNavPlugin {
+    pub fn generate(seed: u32, shape: GalaxyShape, size: GalaxySize) -> Self {
+        UNavPlugin {
+            unav: UNav::generate_shaped(seed, shape, size),
+            goal: GoalKind::default(),
+        }
+    }
+
+    /// Starts from a saved game instead of a fresh map.
+    pub fn load(save: &UNavSave) -> Self {
+        UNavPlugin {
+            unav: UNav::load(save),
+            goal: GoalKind::default(),
+        }
+    }
+
+    pub fn with_goal(mut self, goal: GoalKind) -> Self {
+        self.goal = goal;
+        self
+    }
+}
+
+#[derive(Resource)]
+pub struct UNavToggle {
+    pub active: bool,
+}
+
+#[derive(Event)]
+pub struct ToggleUNav(pub bool);
+
+impl Plugin for UNavPlugin {
+    fn build(&self, app: &mut bevy::app::App) {
+        let central_system_id = self.unav.get_most_central_system().id.clone();
+        // a start without lanes is a broken seed, GalaxyThresholds rejects them
+        let goal = Goal::new(self.goal, &self.unav, &central_system_id)
+            .unwrap_or_else(|err| panic!("can't set up the run goal: {}", err));
+        // the signal's source is hidden and has to be triangulated instead
+        if let (GoalKind::InvestigateSignal, Some(target)) = (goal.kind, goal.target()) {
+            app.insert_resource(Signal::new(target.clone(), self.unav.seed as u64));
+        }
+        app.insert_resource(self.unav.clone())
+            .insert_resource(goal)
+            .init_resource::<QuestLog>()
+            .insert_resource(QuestBoards::new(&self.unav))
+            .init_resource::<GalacticClock>()
+            .insert_resource(Fleets::spawn(&self.unav))
+            .insert_resource(CurrentSystem(central_system_id))
+            .insert_resource(MouseWorldCoords(Vec2::ZERO))
+            .insert_resource(UNavToggle { active: false })
+            .init_resource::<SensorRange>()
+            .init_resource::<Fuel>()
+            .add_event::<ToggleUNav>()
+            .add_event::<HoveredSystemEvent>()
+            .add_event::<UnhoveredSystemEvent>()
+            .add_event::<UNavCameraCommand>()
+            .add_event::<GoalEvent>()
+            .add_event::<JumpTo>()
+            .add_event::<Jumped>()
+            .add_event::<PiratesDefeated>()
+            .add_event::<QuestCompleted>()
+            .add_event::<FleetEncounter>()
+            .add_plugins(ShapePlugin)
+            .add_systems(Startup, setup_camera)
+            .add_systems(Startup, spawn_systems)
+            .add_systems(Startup, spawn_connections)
+            .add_systems(Startup, spawn_territories)
+            .add_systems(Startup, spawn_hazard_overlay)
+            .add_systems(Startup, spawn_system_info_ui)
+            .add_systems(Startup, goal::spawn_goal_ui)
+            .add_systems(Startup, quest::spawn_quest_board_ui)
+            .add_systems(PostStartup, check_toggle)
+            .add_systems(
+                Update,
+                (
+                    swap_view.after(set_visibility),
+                    set_zoom
+                        .after(swap_view)
+                        .run_if(|toggle: Res<UNavToggle>| toggle.active),
+                    change_zoom.run_if(|toggle: Res<UNavToggle>| toggle.active),
+                    drag_pan.run_if(|toggle: Res<UNavToggle>| toggle.active),
+                    keyboard_pan.run_if(|toggle: Res<UNavToggle>| toggle.active),
+                    camera_command_input.run_if(|toggle: Res<UNavToggle>| toggle.active),
+                    on_camera_command,
+                    mouse_world_pos_update.run_if(|toggle: Res<UNavToggle>| toggle.active),
+                    hover_system.run_if(|toggle: Res<UNavToggle>| toggle.active),
+                    on_hover_event.run_if(|toggle: Res<UNavToggle>| toggle.active),
+                    on_unhover_event.run_if(|toggle: Res<UNavToggle>| toggle.active),
+                    jump::click_to_jump.run_if(|toggle: Res<UNavToggle>| toggle.active),
+                ),
+            )
+            .add_systems(Update, jump::jump.after(jump::click_to_jump))
+            .add_systems(Update, set_visibility)
+            .add_systems(
+                Update,
+                update_discovery
+                    .run_if(resource_changed::<CurrentSystem>.or(resource_changed::<SensorRange>)),
+            )
+            .add_systems(
+                Update,
+                goal::track_goal.run_if(resource_changed::<CurrentSystem>),
+            )
+            .add_systems(
+                Update,
+                goal::draw_goal
+                    .after(goal::track_goal)
+                    .run_if(resource_changed::<Goal>),
+            )
+            .add_systems(
+                Update,
+                signal::scan_signal
+                    .after(jump::jump)
+                    .run_if(resource_exists::<Signal>),
+            )
+            .add_systems(
+                Update,
+                signal::draw_signal
+                    .after(signal::scan_signal)
+                    .run_if(resource_exists::<Signal>.and(resource_changed::<Signal>)),
+            )
+            .add_systems(
+                Update,
+                (
+                    quest::accept_quest_input.run_if(|toggle: Res<UNavToggle>| toggle.active),
+                    quest::fight_pirates.after(fleet::advance_clock),
+                    quest::track_quests,
+                    quest::update_quest_board
+                        .run_if(resource_changed::<QuestLog>.or(resource_changed::<CurrentSystem>)),
+                    quest::draw_quests
+                        .after(update_discovery)
+                        .run_if(resource_changed::<QuestLog>.or(resource_changed::<UNav>)),
+                )
+                    .chain(),
+            )
+            .add_systems(
+                Update,
+                (
+                    fleet::advance_clock.after(jump::jump),
+                    fleet::draw_fleets.run_if(
+                        resource_changed::<Fleets>
+                            .or(resource_changed::<SensorRange>)
+                            .or(resource_changed::<UNavToggle>),
+                    ),
+                )
+                    .chain(),
+            )
+            .add_systems(
+                Update,
+                apply_fog
+                    .after(set_visibility)
+                    .after(update_discovery)
+                    .run_if(resource_changed::<UNav>.or(resource_changed::<UNavToggle>)),
+            );
+    }
+}
+
+#[derive(Component)]
+pub(crate) struct UNavEntity;
+
+fn check_toggle(unav_toggle: Res<UNavToggle>, mut query: Query<&mut Visibility, With<UNavEntity>>) {
+    for mut visibility in query.iter_mut() {
+        *visibility = if unav_toggle.active {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        }
+    }
+}
+
+fn set_visibility(
+    mut toggle_unav: EventReader<ToggleUNav>,
+    mut unav_toggle: ResMut<UNavToggle>,
+    mut query: Query<&mut Visibility, With<UNavEntity>>,
+) {
+    for ToggleUNav(toggle) in toggle_unav.read() {
+        for mut visibility in query.iter_mut() {
+            *visibility = if *toggle {
+                unav_toggle.active = true;
+                Visibility::Visible
+            } else {
+                unav_toggle.active = false;
+                Visibility::Hidden
+            }
+        }
+    }
+}
+
+/// Marker so we can find and update this text later
+#[derive(Component)]
+pub struct SystemInfoText;
+
+pub fn spawn_system_info_ui(mut commands: Commands) {
+    commands
+        .spawn((Node {
+            position_type: PositionType::Absolute,
+            width: Val::Px(300.0),
+            height: Val::Auto,
+            top: Val::Px(20.0),
+            right: Val::Px(20.0),
+            padding: UiRect::all(Val::Px(10.0)),
+            flex_direction: FlexDirection::Column,
+            ..default()
+        },))
+        .with_children(|parent| {
+            parent.spawn((Text::new("System Info"), SystemInfoText, UNavEntity));
+        });
+}
+
+#[derive(Resource)]
+pub struct CurrentSystem(pub SystemId);
+
+#[derive(Component)]
+pub struct SystemNode {
+    pub id: SystemId,
+}
+
+#[derive(Component)]
+pub struct HoveredSystem(pub SystemId);
+
+fn system_color(system: &System) -> Color {
+    if system.knowledge.is_scanned() {
+        let temperature = system.properties.temperature as f32;
+        Color::srgb(temperature, 0.0, 1.0 - temperature)
+    } else {
+        Color::srgb(0.4, 0.4, 0.4)
+    }
+}
+
+fn spawn_systems(mut commands: Commands, unav: Res<UNav>) {
+    for (_id, node) in unav.systems.iter() {
+        commands.spawn((
+            Sprite {
+                color: system_color(node),
+                custom_size: Some(Vec2::new(5.0, 5.0)),
+                ..default()
+            },
+            Transform::from_xyz(node.position.x as f32, node.position.y as f32, 1.0),
+            SystemNode { id: _id.clone() },
+            UNavEntity,
+        ));
+    }
+}
+
+#[derive(Event)]
+pub struct HoveredSystemEvent(pub SystemId);
+
+#[derive(Event)]
+pub struct UnhoveredSystemEvent;
+
+fn hover_system(
+    mouse_pos: Res<MouseWorldCoords>,
+    unav: Res<UNav>,
+    system_query: Query<(Entity, &SystemNode, Option<&HoveredSystem>)>,
+    mut commands: Commands,
+    mut hover_ew: EventWriter<HoveredSystemEvent>,
+    mut unhover_ew: EventWriter<UnhoveredSystemEvent>,
+) {
+    for (entity, node, hovered) in system_query.iter() {
+        let system = unav.systems.get(&node.id).unwrap();
+        let distance = (system.position.x - mouse_pos.0.x as i64).abs()
+            + (system.position.y - mouse_pos.0.y as i64).abs();
+        if distance < 10 && system.knowledge.is_known() {
+            if hovered.is_none() {
+                commands
+                    .entity(entity)
+                    .insert_if_new(HoveredSystem(node.id.clone()));
+                let _ = hover_ew.send(HoveredSystemEvent(node.id.clone()));
+            }
+        } else if hovered.is_some() {
+            let _ = unhover_ew.send(UnhoveredSystemEvent);
+            commands.entity(entity).remove::<HoveredSystem>();
+        }
+    }
+}
+
+fn on_hover_event(
+    unav: Res<UNav>,
+    current_system: Res<CurrentSystem>,
+    fuel: Res<Fuel>,
+    mut hover_er: EventReader<HoveredSystemEvent>,
+    mut query: Query<&mut Text, With<SystemInfoText>>,
+) {
+    for HoveredSystemEvent(system_id) in hover_er.read() {
+        if let Some(system) = unav.get_system(system_id) {
+            let mut report = system.report().to_string();
+            // hazards along the lane make some jumps dearer than others
+            if let Some(cost) = unav.jump_cost(&current_system.0, system_id) {
+                report += &format!(
+                    "\nJump: {:.0} fuel, {:.0}% encounter chance\n{}",
+                    cost.fuel,
+                    cost.encounter_chance * 100.0,
+                    *fuel
+                );
+            }
+            for mut text in &mut query {
+                **text = report.clone();
+            }
+        }
+    }
+}
+
+fn on_unhover_event(
+    mut unhover_er: EventReader<UnhoveredSystemEvent>,
+    mut query: Query<&mut Text, With<SystemInfoText>>,
+) {
+    for UnhoveredSystemEvent in unhover_er.read() {
+        for mut text in &mut query {
+            **text = "".to_string();
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct UNavConnectionLine {
+    pub from: SystemId,
+    pub to: SystemId,
+}
+
+fn spawn_connections(mut commands: Commands, unav: Res<UNav>) {
+    let connections = unav.connections(CONNECTION_DISTANCE);
+
+    for connection in connections.connections() {
+        let from = &unav.systems[&connection.from];
+        let to = &unav.systems[&connection.to];
+
+        let from_pos = from.position;
+        let to_pos = to.position;
+
+        let line = shapes::Line(
+            Vec2::new(from_pos.x as f32, from_pos.y as f32),
+            Vec2::new(to_pos.x as f32, to_pos.y as f32),
+        );
+
+        let geo = GeometryBuilder::build_as(&line);
+
+        commands.spawn((
+            ShapeBundle {
+                path: geo,
+                ..default()
+            },
+            Stroke::new(Color::WHITE, 1.0),
+            UNavConnectionLine {
+                from: connection.from.clone(),
+                to: connection.to.clone(),
+            },
+            UNavEntity,
+        ));
+    }
+}
+
+#[derive(Component)]
+pub struct TerritoryMarker {
+    pub id: SystemId,
+}
+
+// soft faction-coloured disc behind every owned system
+fn spawn_territories(mut commands: Commands, unav: Res<UNav>) {
+    let disc = shapes::Circle {
+        radius: CONNECTION_DISTANCE as f32 / 2.0,
+        center: Vec2::ZERO,
+    };
+
+    for system in unav.systems.values() {
+        let Some(owner) = system.owner else {
+            continue;
+        };
+
+        commands.spawn((
+            ShapeBundle {
+                path: GeometryBuilder::build_as(&disc),
+                transform: Transform::from_xyz(
+                    system.position.x as f32,
+                    system.position.y as f32,
+                    0.5,
+                ),
+                ..default()
+            },
+            Fill::color(owner.color().with_alpha(0.08)),
+            TerritoryMarker {
+                id: system.id.clone(),
+            },
+            UNavEntity,
+        ));
+    }
+}
+
+// hide what the player hasn't discovered yet and grey out unscanned systems
+#[allow(clippy::type_complexity)]
+fn apply_fog(
+    unav: Res<UNav>,
+    unav_toggle: Res<UNavToggle>,
+    mut systems: Query<(&SystemNode, &mut Sprite, &mut Visibility)>,
+    mut lines: Query<(&UNavConnectionLine, &mut Visibility), Without<SystemNode>>,
+    mut territories: Query<
+        (&TerritoryMarker, &mut Visibility),
+        (Without<SystemNode>, Without<UNavConnectionLine>),
+    >,
+) {
+    let is_known = |id: &SystemId| {
+        unav.get_system(id)
+            .is_some_and(|system| system.knowledge.is_known())
+    };
+
+    for (node, mut sprite, mut visibility) in systems.iter_mut() {
+        let Some(system) = unav.get_system(&node.id) else {
+            continue;
+        };
+        sprite.color = system_color(system);
+        if unav_toggle.active {
+            *visibility = if system.knowledge.is_known() {
+                Visibility::Visible
+            } else {
+                Visibility::Hidden
+            };
+        }
+    }
+
+    if !unav_toggle.active {
+        return;
+    }
+
+    for (line, mut visibility) in lines.iter_mut() {
+        *visibility = if is_known(&line.from) && is_known(&line.to) {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+    }
+
+    // ownership is only known once a system has been scanned
+    for (marker, mut visibility) in territories.iter_mut() {
+        let scanned = unav
+            .get_system(&marker.id)
+            .is_some_and(|system| system.knowledge.is_scanned());
+        *visibility = if scanned {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+/// UNav units covered by one texel of the hazard overlay.
+const HAZARD_TEXEL_SIZE: f32 = 4.0;
+
+// soft coloured clouds where the hazard fields are strong
+fn spawn_hazard_overlay(
+    mut commands: Commands,
+    unav: Res<UNav>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let Some(min) = unav
+        .systems
+        .values()
+        .map(|system| system.position)
+        .reduce(|a, b| a.min(b))
+    else {
+        return;
+    };
+    let max = unav
+        .systems
+        .values()
+        .map(|system| system.position)
+        .fold(min, |a, b| a.max(b));
+
+    let margin = CONNECTION_DISTANCE as f32;
+    let min = min.as_vec2() - margin;
+    let max = max.as_vec2() + margin;
+    let size = max - min;
+    let width = (size.x / HAZARD_TEXEL_SIZE).ceil() as u32;
+    let height = (size.y / HAZARD_TEXEL_SIZE).ceil() as u32;
+
+    let mut data = Vec::with_capacity((width * height * 4) as usize);
+    for row in 0..height {
+        for col in 0..width {
+            // image rows go top to bottom, the map's y axis goes up
+            let point = Vec2::new(
+                min.x + (col as f32 + 0.5) * HAZARD_TEXEL_SIZE,
+                max.y - (row as f32 + 0.5) * HAZARD_TEXEL_SIZE,
+            );
+            let hazards = unav.hazards.at(point.as_dvec2());
+
+            let mut color = Vec3::ZERO;
+            let mut alpha: f32 = 0.0;
+            for kind in HazardKind::ALL {
+                let intensity = hazards.get(kind) as f32;
+                let tint = match kind {
+                    HazardKind::IonStorm => Vec3::new(0.3, 0.8, 1.0),
+                    HazardKind::Nebula => Vec3::new(0.6, 0.3, 0.9),
+                    HazardKind::Radiation => Vec3::new(0.7, 1.0, 0.2),
+                };
+                color += tint * intensity;
+                alpha = alpha.max(intensity);
+            }
+            let color = color / color.max_element().max(1.0);
+
+            data.extend_from_slice(&[
+                (color.x * 255.0) as u8,
+                (color.y * 255.0) as u8,
+                (color.z * 255.0) as u8,
+                (alpha * 0.3 * 255.0) as u8,
+            ]);
+        }
+    }
+
+    let mut image = Image::new(
+        Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+    image.sampler = ImageSampler::linear();
+
+    let center = (min + max) / 2.0;
+    commands.spawn((
+        Sprite {
+            image: images.add(image),
+            custom_size: Some(size),
+            ..default()
+        },
+        Transform::from_xyz(center.x, center.y, 0.25),
+        UNavEntity,
+    ));
+}
+
+#[derive(Resource, Default)]
+pub struct MouseWorldCoords(pub Vec2);
+
+pub fn mouse_world_pos_update(
+    mut mycoords: ResMut<MouseWorldCoords>,
+    q_window: Query<&Window, With<PrimaryWindow>>,
+    q_camera: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+) {
+    let (camera, camera_transform) = q_camera.single();
+
+    let window = q_window.single();
+
+    if let Some(world_position) = window
+        .cursor_position()
+        .map(|cursor| camera.viewport_to_world(camera_transform, cursor))
+        .map(|ray| ray.unwrap().origin.truncate())
+    {
+        mycoords.0 = world_position;
+    }
+}
@@ -0,0 +1,558 @@
+use std::{collections::HashMap, fmt::Display};
+
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    contents::{LootKind, ResourceKind, StationService},
+    fleet::{FleetEncounter, FleetKind},
+    stats::jumps_from,
+    unav::CONNECTION_DISTANCE,
+    CurrentSystem, SystemComponents, SystemId, UNav, UNavEntity, UNavToggle,
+};
+
+/// Mixed into `System::seed` so quests have their own random stream.
+const QUEST_SEED_SALT: u64 = 0x7175_6573_7473;
+
+/// Farthest a quest target can be from the station offering it, in jumps.
+const QUEST_RANGE_JUMPS: usize = 6;
+
+/// Credits paid per jump to the target, before the danger bonus.
+const CREDITS_PER_JUMP: u32 = 40;
+
+const GIVER_ICON_RADIUS: f32 = 4.0;
+const TARGET_ICON_RADIUS: f32 = 6.0;
+const QUEST_COLOR: Color = Color::srgb(1.0, 0.8, 0.1);
+
+const ACCEPT_KEYS: [KeyCode; 9] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum QuestKind {
+    /// Carry cargo to another station. Done on arrival.
+    DeliverCargo(ResourceKind),
+    /// Clear out a pirate system. Done when `PiratesDefeated` is sent for it.
+    DestroyPirates,
+    /// Take a look at an anomaly. Done on arrival.
+    ScoutAnomaly,
+}
+
+impl QuestKind {
+    /// What a system needs to be a target for this kind of quest.
+    fn target_component(&self) -> SystemComponents {
+        match self {
+            QuestKind::DeliverCargo(_) => SystemComponents::Station,
+            QuestKind::DestroyPirates => SystemComponents::Pirates,
+            QuestKind::ScoutAnomaly => SystemComponents::Anomaly,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Quest {
+    /// Unique within the map, `<giver id>-<index>`.
+    pub id: String,
+    pub giver: SystemId,
+    pub station: String,
+    pub kind: QuestKind,
+    pub target: SystemId,
+    pub reward: LootKind,
+    pub amount: u32,
+}
+
+impl Display for Quest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            QuestKind::DeliverCargo(resource) => write!(f, "Deliver {:?}", resource)?,
+            QuestKind::DestroyPirates => write!(f, "Destroy pirates")?,
+            QuestKind::ScoutAnomaly => write!(f, "Scout anomaly")?,
+        }
+        match self.reward {
+            LootKind::Credits => write!(f, " for {} credits", self.amount),
+            LootKind::Resource(resource) => write!(f, " for {} {:?}", self.amount, resource),
+            LootKind::Part => write!(f, " for {} parts", self.amount),
+        }
+    }
+}
+
+impl UNav {
+    /// Quests posted at a system's stations. Every station posts one,
+    /// stations offering `StationService::Quests` post a few more.
+    /// `adjacency` is the map's jump lanes, see `Connections::adjacency`.
+    pub fn quests_at(
+        &self,
+        id: &SystemId,
+        adjacency: &HashMap<SystemId, Vec<SystemId>>,
+    ) -> Vec<Quest> {
+        let Some(giver) = self.get_system(id) else {
+            return Vec::new();
+        };
+        if !giver.properties.r#type.contains(&SystemComponents::Station) {
+            return Vec::new();
+        }
+
+        let mut in_range: Vec<(SystemId, usize)> = jumps_from(id, adjacency)
+            .into_iter()
+            .filter(|(_, jumps)| (1..=QUEST_RANGE_JUMPS).contains(jumps))
+            .collect();
+        in_range.sort_by(|a, b| a.0 .0.cmp(&b.0 .0));
+
+        let mut rng = StdRng::seed_from_u64(giver.seed ^ QUEST_SEED_SALT);
+        let mut quests = Vec::new();
+        for station in giver.contents.stations.iter() {
+            let count = if station.services.contains(&StationService::Quests) {
+                rng.gen_range(2..=3)
+            } else {
+                1
+            };
+            for _ in 0..count {
+                let kind = *[
+                    QuestKind::DeliverCargo(
+                        *[ResourceKind::Iron, ResourceKind::Nickel, ResourceKind::Ice]
+                            .choose(&mut rng)
+                            .unwrap(),
+                    ),
+                    QuestKind::DestroyPirates,
+                    QuestKind::ScoutAnomaly,
+                ]
+                .choose(&mut rng)
+                .unwrap();
+
+                let targets: Vec<&(SystemId, usize)> = in_range
+                    .iter()
+                    .filter(|(target, _)| {
+                        self.get_system(target).is_some_and(|system| {
+                            system.properties.r#type.contains(&kind.target_component())
+                        })
+                    })
+                    .collect();
+                let Some((target, jumps)) = targets.choose(&mut rng) else {
+                    continue;
+                };
+
+                let danger = self
+                    .get_system(target)
+                    .unwrap()
+                    .properties
+                    .reward_multiplier();
+                let credits = (CREDITS_PER_JUMP as f64 * *jumps as f64 * danger).round() as u32;
+                let (reward, amount) = match rng.gen_range(0..4) {
+                    0 => (LootKind::Part, 1 + credits / 200),
+                    1 => (LootKind::Resource(ResourceKind::Platinum), 1 + credits / 40),
+                    _ => (LootKind::Credits, credits),
+                };
+
+                quests.push(Quest {
+                    id: format!("{}-{}", id.0, quests.len()),
+                    giver: id.clone(),
+                    station: station.name.clone(),
+                    kind,
+                    target: target.clone(),
+                    reward,
+                    amount,
+                });
+            }
+        }
+        quests
+    }
+}
+
+/// Every station's quests. Worked out once, they only depend on what the
+/// map was generated with.
+#[derive(Resource, Clone, Debug, Default, PartialEq)]
+pub struct QuestBoards(pub HashMap<SystemId, Vec<Quest>>);
+
+impl QuestBoards {
+    pub fn new(unav: &UNav) -> Self {
+        let adjacency = unav.connections(CONNECTION_DISTANCE).adjacency();
+        let boards = unav
+            .systems
+            .keys()
+            .map(|id| (id.clone(), unav.quests_at(id, &adjacency)))
+            .filter(|(_, quests)| !quests.is_empty())
+            .collect();
+        Self(boards)
+    }
+
+    pub fn at(&self, id: &SystemId) -> &[Quest] {
+        self.0.get(id).map_or(&[], Vec::as_slice)
+    }
+}
+
+/// Accepted quests and what happened to them.
+#[derive(Resource, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct QuestLog {
+    pub active: Vec<Quest>,
+    pub completed: Vec<Quest>,
+}
+
+impl QuestLog {
+    /// Whether a quest has been accepted, done or not.
+    pub fn is_taken(&self, quest: &Quest) -> bool {
+        self.active
+            .iter()
+            .chain(self.completed.iter())
+            .any(|taken| taken.id == quest.id)
+    }
+
+    pub fn accept(&mut self, quest: Quest) -> bool {
+        if self.is_taken(&quest) {
+            return false;
+        }
+        self.active.push(quest);
+        true
+    }
+
+    /// Quests at `id` that haven't been taken yet.
+    pub fn available(&self, boards: &QuestBoards, id: &SystemId) -> Vec<Quest> {
+        boards
+            .at(id)
+            .iter()
+            .filter(|quest| !self.is_taken(quest))
+            .cloned()
+            .collect()
+    }
+
+    /// Completes active quests matching `done`, returning them.
+    fn complete(&mut self, done: impl Fn(&Quest) -> bool) -> Vec<Quest> {
+        let (finished, active): (Vec<Quest>, Vec<Quest>) =
+            self.active.drain(..).partition(|quest| done(quest));
+        self.active = active;
+        self.completed.extend(finished.iter().cloned());
+        finished
+    }
+
+    /// The player arrived at `id`: deliveries and scouting are done.
+    pub fn arrive(&mut self, id: &SystemId) -> Vec<Quest> {
+        self.complete(|quest| {
+            quest.target == *id
+                && matches!(
+                    quest.kind,
+                    QuestKind::DeliverCargo(_) | QuestKind::ScoutAnomaly
+                )
+        })
+    }
+
+    pub fn pirates_defeated(&mut self, id: &SystemId) -> Vec<Quest> {
+        self.complete(|quest| quest.target == *id && quest.kind == QuestKind::DestroyPirates)
+    }
+}
+
+/// Send when the pirates in a system have been beaten.
+#[derive(Event, Clone, Debug)]
+pub struct PiratesDefeated(pub SystemId);
+
+/// A quest was finished, hand out its reward.
+#[derive(Event, Clone, Debug)]
+pub struct QuestCompleted(pub Quest);
+
+#[derive(Component)]
+pub(crate) struct QuestMarker;
+
+#[derive(Component)]
+pub(crate) struct QuestBoardText;
+
+pub(crate) fn spawn_quest_board_ui(mut commands: Commands) {
+    commands
+        .spawn(Node {
+            position_type: PositionType::Absolute,
+            width: Val::Px(400.0),
+            bottom: Val::Px(20.0),
+            left: Val::Px(20.0),
+            padding: UiRect::all(Val::Px(10.0)),
+            flex_direction: FlexDirection::Column,
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn((Text::new(""), QuestBoardText, UNavEntity));
+        });
+}
+
+pub(crate) fn track_quests(
+    current_system: Res<CurrentSystem>,
+    mut quest_log: ResMut<QuestLog>,
+    mut defeated: EventReader<PiratesDefeated>,
+    mut completed: EventWriter<QuestCompleted>,
+) {
+    let mut finished = Vec::new();
+    if current_system.is_changed() {
+        finished.extend(quest_log.arrive(&current_system.0));
+    }
+    for PiratesDefeated(id) in defeated.read() {
+        finished.extend(quest_log.pirates_defeated(id));
+    }
+    completed.send_batch(finished.into_iter().map(QuestCompleted));
+}
+
+// there's no combat on the map, so running into raiders in their own
+// system is what clears it out
+pub(crate) fn fight_pirates(
+    current_system: Res<CurrentSystem>,
+    mut encounters: EventReader<FleetEncounter>,
+    mut defeated: EventWriter<PiratesDefeated>,
+) {
+    if encounters.read().any(|FleetEncounter(fleet)| {
+        fleet.kind == FleetKind::Raider && fleet.location == current_system.0
+    }) {
+        defeated.send(PiratesDefeated(current_system.0.clone()));
+    }
+}
+
+pub(crate) fn accept_quest_input(
+    boards: Res<QuestBoards>,
+    current_system: Res<CurrentSystem>,
+    input: Res<ButtonInput<KeyCode>>,
+    mut quest_log: ResMut<QuestLog>,
+) {
+    let Some(index) = ACCEPT_KEYS.iter().position(|key| input.just_pressed(*key)) else {
+        return;
+    };
+    if let Some(quest) = quest_log
+        .available(&boards, &current_system.0)
+        .into_iter()
+        .nth(index)
+    {
+        quest_log.accept(quest);
+    }
+}
+
+/// Lists the current system's quests and the active ones.
+pub(crate) fn update_quest_board(
+    unav: Res<UNav>,
+    boards: Res<QuestBoards>,
+    current_system: Res<CurrentSystem>,
+    quest_log: Res<QuestLog>,
+    mut text: Query<&mut Text, With<QuestBoardText>>,
+) {
+    let system_name = |id: &SystemId| {
+        unav.get_system(id).map_or("?".to_string(), |system| {
+            system.properties.name.name.clone()
+        })
+    };
+
+    let mut board = String::new();
+    let available = quest_log.available(&boards, &current_system.0);
+    if !available.is_empty() {
+        board.push_str("Quest board:\n");
+        for (i, quest) in available.iter().take(ACCEPT_KEYS.len()).enumerate() {
+            board.push_str(&format!(
+                "[{}] {} at {} ({})\n",
+                i + 1,
+                quest,
+                system_name(&quest.target),
+                quest.station
+            ));
+        }
+    }
+    if !quest_log.active.is_empty() {
+        board.push_str("Active quests:\n");
+        for quest in quest_log.active.iter() {
+            board.push_str(&format!(" - {} at {}\n", quest, system_name(&quest.target)));
+        }
+    }
+
+    for mut text in text.iter_mut() {
+        **text = board.clone();
+    }
+}
+
+/// Diamonds on scanned systems with quests on offer, rings on the targets of
+/// active quests.
+pub(crate) fn draw_quests(
+    mut commands: Commands,
+    unav: Res<UNav>,
+    boards: Res<QuestBoards>,
+    quest_log: Res<QuestLog>,
+    unav_toggle: Res<UNavToggle>,
+    markers: Query<Entity, With<QuestMarker>>,
+) {
+    for marker in markers.iter() {
+        commands.entity(marker).despawn();
+    }
+    let visibility = if unav_toggle.active {
+        Visibility::Visible
+    } else {
+        Visibility::Hidden
+    };
+
+    let diamond = shapes::RegularPolygon {
+        sides: 4,
+        feature: shapes::RegularPolygonFeature::Radius(GIVER_ICON_RADIUS),
+        ..default()
+    };
+    for (id, quests) in boards.0.iter() {
+        let Some(system) = unav.get_system(id) else {
+            continue;
+        };
+        if !system.knowledge.is_scanned() || quests.iter().all(|quest| quest_log.is_taken(quest)) {
+            continue;
+        }
+        commands.spawn((
+            ShapeBundle {
+                path: GeometryBuilder::build_as(&diamond),
+                transform: Transform::from_xyz(
+                    system.position.x as f32,
+                    system.position.y as f32 + GIVER_ICON_RADIUS * 2.0,
+                    2.0,
+                ),
+                visibility,
+                ..default()
+            },
+            Stroke::new(QUEST_COLOR, 1.0),
+            QuestMarker,
+            UNavEntity,
+        ));
+    }
+
+    let ring = shapes::Circle {
+        radius: TARGET_ICON_RADIUS,
+        center: Vec2::ZERO,
+    };
+    for quest in quest_log.active.iter() {
+        let Some(target) = unav.get_system(&quest.target) else {
+            continue;
+        };
+        commands.spawn((
+            ShapeBundle {
+                path: GeometryBuilder::build_as(&ring),
+                transform: Transform::from_xyz(
+                    target.position.x as f32,
+                    target.position.y as f32,
+                    2.0,
+                ),
+                visibility,
+                ..default()
+            },
+            Stroke::new(QUEST_COLOR, 1.0),
+            QuestMarker,
+            UNavEntity,
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn first_quest(boards: &QuestBoards, kind: fn(&QuestKind) -> bool) -> Quest {
+        let mut ids: Vec<&SystemId> = boards.0.keys().collect();
+        ids.sort_by(|a, b| a.0.cmp(&b.0));
+        ids.into_iter()
+            .flat_map(|id| boards.at(id))
+            .find(|quest| kind(&quest.kind))
+            .unwrap()
+            .clone()
+    }
+
+    #[test]
+    fn stations_post_quests_for_matching_targets() {
+        let unav = UNav::generate(19940131);
+        let boards = QuestBoards::new(&unav);
+        for quests in boards.0.values() {
+            for quest in quests {
+                let target = unav.get_system(&quest.target).unwrap();
+                assert!(target
+                    .properties
+                    .r#type
+                    .contains(&quest.kind.target_component()));
+                assert!(quest.amount > 0);
+            }
+        }
+        assert_eq!(QuestBoards::new(&unav), boards);
+    }
+
+    #[test]
+    fn quests_complete() {
+        let boards = QuestBoards::new(&UNav::generate(19940131));
+        let delivery = first_quest(&boards, |kind| matches!(kind, QuestKind::DeliverCargo(_)));
+        let scouting = first_quest(&boards, |kind| *kind == QuestKind::ScoutAnomaly);
+        let bounty = first_quest(&boards, |kind| *kind == QuestKind::DestroyPirates);
+
+        let mut log = QuestLog::default();
+        assert!(log.accept(delivery.clone()));
+        assert!(!log.accept(delivery.clone()));
+        assert!(log.accept(scouting.clone()));
+        assert!(log.accept(bounty.clone()));
+
+        assert_eq!(log.arrive(&delivery.target), vec![delivery.clone()]);
+        assert_eq!(log.arrive(&scouting.target), vec![scouting]);
+        // just passing through doesn't beat the pirates
+        assert!(log.arrive(&bounty.target).is_empty());
+        assert_eq!(log.pirates_defeated(&bounty.target), vec![bounty]);
+        assert!(log.active.is_empty());
+        assert!(!log.available(&boards, &delivery.giver).contains(&delivery));
+    }
+
+    #[test]
+    fn beating_pirates_pays_out_bounties() {
+        let unav = UNav::generate(19940131);
+        let boards = QuestBoards::new(&unav);
+        let bounty = first_quest(&boards, |kind| *kind == QuestKind::DestroyPirates);
+        let mut log = QuestLog::default();
+        log.accept(bounty.clone());
+
+        let mut app = App::new();
+        app.insert_resource(CurrentSystem(bounty.giver.clone()))
+            .insert_resource(log)
+            .add_event::<PiratesDefeated>()
+            .add_event::<QuestCompleted>()
+            .add_systems(Update, track_quests);
+        app.update();
+        assert_eq!(app.world().resource::<QuestLog>().active.len(), 1);
+
+        app.world_mut()
+            .send_event(PiratesDefeated(bounty.target.clone()));
+        app.update();
+        let log = app.world().resource::<QuestLog>();
+        assert!(log.active.is_empty());
+        assert_eq!(log.completed, vec![bounty]);
+        let events = app.world().resource::<Events<QuestCompleted>>();
+        assert_eq!(events.len(), 1);
+    }
+
+    #[test]
+    fn raiders_met_at_the_target_count_as_beaten() {
+        let unav = UNav::generate(19940131);
+        let boards = QuestBoards::new(&unav);
+        let bounty = first_quest(&boards, |kind| *kind == QuestKind::DestroyPirates);
+        let mut log = QuestLog::default();
+        log.accept(bounty.clone());
+        let raiders = |location: &SystemId| {
+            FleetEncounter(crate::Fleet {
+                id: 0,
+                kind: FleetKind::Raider,
+                location: location.clone(),
+                destination: location.clone(),
+            })
+        };
+
+        let mut app = App::new();
+        app.insert_resource(CurrentSystem(bounty.giver.clone()))
+            .insert_resource(log)
+            .add_event::<FleetEncounter>()
+            .add_event::<PiratesDefeated>()
+            .add_event::<QuestCompleted>()
+            .add_systems(Update, (fight_pirates, track_quests).chain());
+
+        // raiders somewhere else don't count
+        app.world_mut().send_event(raiders(&bounty.giver));
+        app.update();
+        assert_eq!(app.world().resource::<QuestLog>().active.len(), 1);
+
+        app.world_mut().resource_mut::<CurrentSystem>().0 = bounty.target.clone();
+        app.world_mut().send_event(raiders(&bounty.target));
+        app.update();
+        assert_eq!(app.world().resource::<QuestLog>().completed, vec![bounty]);
+    }
+}
@@ -0,0 +1,303 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use bevy_ecs::system::Resource;
+use bevy_log::warn;
+use bevy_math::IVec2;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    contents::{ResourceKind, ResourceYield},
+    discovery::Knowledge,
+    faction::Faction,
+    shape::{GalaxyShape, GalaxySize},
+    SystemId, UNav,
+};
+
+/// A resource deposit that has been mined down from what the map generated.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Depletion {
+    pub system: SystemId,
+    /// Index into `SystemContents::asteroid_belts`.
+    pub belt: usize,
+    pub resource: ResourceKind,
+    pub remaining: u32,
+}
+
+/// What the player changed in one chunk of a system's scene. Chunks are
+/// regenerated from the system seed every time they load, this is applied
+/// on top.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChunkDelta {
+    /// What has been mined out of each rock, by the rock's index in the
+    /// chunk.
+    pub mined: BTreeMap<usize, Vec<ResourceYield>>,
+    /// Indices of the containers that have been emptied.
+    pub emptied: BTreeSet<usize>,
+}
+
+impl ChunkDelta {
+    pub fn mine(&mut self, rock: usize, resource: ResourceKind, amount: u32) {
+        let mined = self.mined.entry(rock).or_default();
+        match mined.iter_mut().find(|y| y.resource == resource) {
+            Some(deposit) => deposit.amount += amount,
+            None => mined.push(ResourceYield { resource, amount }),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.mined.is_empty() && self.emptied.is_empty()
+    }
+}
+
+/// Changes to every chunk the player has touched, keyed by system seed and
+/// chunk coordinate.
+#[derive(Resource, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChunkDeltas(pub HashMap<(u64, IVec2), ChunkDelta>);
+
+impl ChunkDeltas {
+    pub fn get(&self, seed: u64, coord: IVec2) -> Option<&ChunkDelta> {
+        self.0.get(&(seed, coord))
+    }
+
+    pub fn entry(&mut self, seed: u64, coord: IVec2) -> &mut ChunkDelta {
+        self.0.entry((seed, coord)).or_default()
+    }
+}
+
+/// Everything needed to restore a `UNav`: the generation parameters plus
+/// whatever the player changed since. Everything else is regenerated.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct UNavSave {
+    pub seed: u32,
+    pub shape: GalaxyShape,
+    pub size: GalaxySize,
+    pub knowledge: Vec<(SystemId, Knowledge)>,
+    pub owners: Vec<(SystemId, Option<Faction>)>,
+    pub depletions: Vec<Depletion>,
+    /// Rocks mined and containers emptied in system scenes, by system seed
+    /// and chunk coordinate.
+    #[serde(default)]
+    pub chunks: Vec<(u64, IVec2, ChunkDelta)>,
+}
+
+impl UNavSave {
+    pub fn to_ron(&self) -> Result<String, ron::Error> {
+        ron::to_string(self)
+    }
+
+    pub fn from_ron(save: &str) -> Result<Self, ron::error::SpannedError> {
+        ron::from_str(save)
+    }
+}
+
+impl UNav {
+    /// Regenerates the map this UNav came from, without any player changes.
+    pub fn regenerate(&self) -> UNav {
+        UNav::generate_shaped(self.seed, self.shape, self.size)
+    }
+
+    /// Diffs against a freshly generated map so only player changes are kept.
+    pub fn save(&self) -> UNavSave {
+        let fresh = self.regenerate();
+
+        let mut ids: Vec<&SystemId> = self.systems.keys().collect();
+        ids.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut knowledge = Vec::new();
+        let mut owners = Vec::new();
+        let mut depletions = Vec::new();
+        for id in ids {
+            let system = &self.systems[id];
+            let Some(original) = fresh.get_system(id) else {
+                continue;
+            };
+            if system.knowledge != original.knowledge {
+                knowledge.push((id.clone(), system.knowledge));
+            }
+            if system.owner != original.owner {
+                owners.push((id.clone(), system.owner));
+            }
+            let belts = system
+                .contents
+                .asteroid_belts
+                .iter()
+                .zip(original.contents.asteroid_belts.iter());
+            for (belt, (current, original)) in belts.enumerate() {
+                for (deposit, generated) in current.yields.iter().zip(original.yields.iter()) {
+                    if deposit.amount != generated.amount {
+                        depletions.push(Depletion {
+                            system: id.clone(),
+                            belt,
+                            resource: deposit.resource,
+                            remaining: deposit.amount,
+                        });
+                    }
+                }
+            }
+        }
+
+        let mut chunks: Vec<(u64, IVec2, ChunkDelta)> = self
+            .chunks
+            .0
+            .iter()
+            .filter(|(_, delta)| !delta.is_empty())
+            .map(|((seed, coord), delta)| (*seed, *coord, delta.clone()))
+            .collect();
+        chunks.sort_by_key(|(seed, coord, _)| (*seed, coord.x, coord.y));
+
+        UNavSave {
+            seed: self.seed,
+            shape: self.shape,
+            size: self.size,
+            knowledge,
+            owners,
+            depletions,
+            chunks,
+        }
+    }
+
+    /// Regenerates the map from the save's seed and replays its changes.
+    /// Changes to systems that don't exist in the map are skipped.
+    pub fn load(save: &UNavSave) -> UNav {
+        let mut unav = UNav::generate_shaped(save.seed, save.shape, save.size);
+
+        for (id, knowledge) in save.knowledge.iter() {
+            match unav.systems.get_mut(id) {
+                Some(system) => system.knowledge = *knowledge,
+                None => warn!("Save has knowledge of unknown system {:?}", id),
+            }
+        }
+
+        for (id, owner) in save.owners.iter() {
+            match unav.systems.get_mut(id) {
+                Some(system) => system.set_owner(*owner),
+                None => warn!("Save has owner of unknown system {:?}", id),
+            }
+        }
+
+        for depletion in save.depletions.iter() {
+            let deposit = unav
+                .systems
+                .get_mut(&depletion.system)
+                .and_then(|system| system.contents.asteroid_belts.get_mut(depletion.belt))
+                .and_then(|belt| {
+                    belt.yields
+                        .iter_mut()
+                        .find(|deposit| deposit.resource == depletion.resource)
+                });
+            match deposit {
+                Some(deposit) => deposit.amount = depletion.remaining,
+                None => warn!("Save has depletion of unknown deposit {:?}", depletion),
+            }
+        }
+
+        for (seed, coord, delta) in save.chunks.iter() {
+            unav.chunks.0.insert((*seed, *coord), delta.clone());
+        }
+
+        unav
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_math::I64Vec2;
+
+    use super::*;
+    use crate::{Station, SystemContents};
+
+    #[test]
+    fn untouched_map_saves_nothing() {
+        let unav = UNav::generate(19940131);
+        let save = unav.save();
+        assert!(save.knowledge.is_empty());
+        assert!(save.owners.is_empty());
+        assert!(save.depletions.is_empty());
+        assert!(save.chunks.is_empty());
+    }
+
+    #[test]
+    fn save_round_trips() {
+        let mut unav = UNav::generate_shaped(7, GalaxyShape::Ring, GalaxySize::Small);
+
+        let start = unav.get_most_central_system().id.clone();
+        unav.systems.get_mut(&start).unwrap().knowledge = Knowledge::Visited;
+
+        let mut ids: Vec<SystemId> = unav.systems.keys().cloned().collect();
+        ids.sort_by(|a, b| a.0.cmp(&b.0));
+        unav.systems
+            .get_mut(&ids[0])
+            .unwrap()
+            .set_owner(Some(Faction::Pirates));
+
+        let mined = unav
+            .systems
+            .values_mut()
+            .find_map(|system| system.contents.asteroid_belts.first_mut())
+            .map(|belt| {
+                let resource = belt.yields[0].resource;
+                belt.mine(resource, 1)
+            });
+        assert_eq!(mined, Some(1));
+
+        let chunk = unav.chunks.entry(99, IVec2::new(-2, 3));
+        chunk.mine(4, ResourceKind::Ice, 12);
+        chunk.mine(4, ResourceKind::Ice, 3);
+        chunk.emptied.insert(1);
+
+        let text = unav.save().to_ron().unwrap();
+        let loaded = UNav::load(&UNavSave::from_ron(&text).unwrap());
+
+        assert_eq!(loaded.systems, unav.systems);
+        assert_eq!(loaded.chunks, unav.chunks);
+        assert_eq!(
+            loaded.chunks.get(99, IVec2::new(-2, 3)).unwrap().mined[&4],
+            vec![ResourceYield {
+                resource: ResourceKind::Ice,
+                amount: 15
+            }]
+        );
+        assert_eq!(
+            loaded.save().depletions.len(),
+            1,
+            "mined belt should be the only depletion"
+        );
+    }
+
+    #[test]
+    fn unclaimed_systems_round_trip() {
+        let mut unav = UNav::generate(19940131);
+        let fresh = unav.clone();
+
+        let claimed = unav
+            .systems
+            .values_mut()
+            .find(|system| system.owner.is_some() && !system.contents.stations.is_empty())
+            .unwrap();
+        let id = claimed.id.clone();
+        claimed.set_owner(None);
+        let stations = claimed.contents.stations.clone();
+
+        // back to the factions an unclaimed system generates with
+        let original = &fresh.systems[&id];
+        let generated = SystemContents::generate(original.seed, &original.properties);
+        let factions =
+            |stations: &[Station]| stations.iter().map(|s| s.faction).collect::<Vec<_>>();
+        assert_eq!(factions(&stations), factions(&generated.stations));
+
+        let text = unav.save().to_ron().unwrap();
+        let loaded = UNav::load(&UNavSave::from_ron(&text).unwrap());
+        assert_eq!(loaded.systems, unav.systems);
+        assert_eq!(loaded.systems[&id].owner, None);
+    }
+
+    #[test]
+    fn full_unav_serializes() {
+        let unav = UNav::generate(3);
+        let text = ron::to_string(&unav).unwrap();
+        let loaded: UNav = ron::from_str(&text).unwrap();
+        assert_eq!(loaded.systems, unav.systems);
+        let point = I64Vec2::new(10, 20);
+        assert_eq!(loaded.hazards_at(point), unav.hazards_at(point));
+    }
+}
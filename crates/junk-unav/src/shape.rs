@@ -0,0 +1,181 @@
+use bevy_math::I64Vec2;
+use noise::{Fbm, MultiFractal, NoiseFn, Perlin};
+use serde::{Deserialize, Serialize};
+
+/// Densities between these are faded, above is solid and below is empty.
+const EDGE_LOW: f64 = 0.25;
+const EDGE_HIGH: f64 = 0.45;
+
+/// Density of the sparse systems between archipelago islands.
+const ARCHIPELAGO_STRAIT_DENSITY: f64 = 0.2;
+
+fn smoothstep(low: f64, high: f64, x: f64) -> f64 {
+    let t = ((x - low) / (high - low)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Overall layout of the systems on the UNav.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum GalaxyShape {
+    /// Uniform square, the original layout.
+    #[default]
+    Square,
+    /// A bright core with `arms` logarithmic spiral arms.
+    Spiral { arms: u32 },
+    /// A hollow ring around an empty centre.
+    Ring,
+    /// Islands of systems separated by empty space.
+    Archipelago,
+    /// A squashed disc that thins out towards the edge.
+    Elliptical,
+}
+
+/// Side length of the square the galaxy is sampled in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GalaxySize {
+    #[default]
+    Small,
+    Medium,
+    Large,
+}
+
+impl GalaxySize {
+    pub const ALL: [GalaxySize; 3] = [GalaxySize::Small, GalaxySize::Medium, GalaxySize::Large];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GalaxySize::Small => "small",
+            GalaxySize::Medium => "medium",
+            GalaxySize::Large => "large",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<GalaxySize> {
+        GalaxySize::ALL.into_iter().find(|size| size.name() == name)
+    }
+
+    pub fn extent(&self) -> isize {
+        match self {
+            GalaxySize::Small => 256,
+            GalaxySize::Medium => 512,
+            GalaxySize::Large => 1024,
+        }
+    }
+}
+
+/// Turns a shape into a density function for `PoissonSampler::thinned`.
+pub(crate) struct GalaxyDensity {
+    shape: GalaxyShape,
+    half_extent: f64,
+    noise: Fbm<Perlin>,
+}
+
+impl GalaxyDensity {
+    pub fn new(shape: GalaxyShape, size: GalaxySize, seed: u32) -> Self {
+        Self {
+            shape,
+            half_extent: size.extent() as f64 / 2.0,
+            noise: Fbm::<Perlin>::new(seed).set_octaves(4).set_frequency(2.0),
+        }
+    }
+
+    /// Density at a point in centered UNav coordinates, 0.0 to 1.0.
+    pub fn at(&self, point: I64Vec2) -> f64 {
+        // normalise so the sampling square spans -1..1
+        let u = point.x as f64 / self.half_extent;
+        let v = point.y as f64 / self.half_extent;
+        let r = (u * u + v * v).sqrt();
+        let noise = self.noise.get([u, v]);
+
+        let density = match self.shape {
+            GalaxyShape::Square => return 1.0,
+            GalaxyShape::Spiral { arms } => {
+                if r > 1.0 {
+                    0.0
+                } else {
+                    let core = 1.0 - r / 0.4;
+                    // wind the arms tighter the further out we go
+                    let theta = v.atan2(u) - 4.0 * r;
+                    let arm = 0.5 + 0.5 * (theta * arms.max(1) as f64).cos();
+                    core.max(arm * (1.0 - r * r))
+                }
+            }
+            GalaxyShape::Ring => {
+                let band = ((r - 0.7) / 0.18).powi(2);
+                (-band).exp()
+            }
+            GalaxyShape::Archipelago => {
+                // the crests of the noise become islands, with a sparse
+                // scattering of systems in between to hop across
+                return if r > 1.0 {
+                    0.0
+                } else if noise < 0.0 {
+                    ARCHIPELAGO_STRAIT_DENSITY
+                } else {
+                    1.0
+                };
+            }
+            GalaxyShape::Elliptical => {
+                let e = (u * u + (v / 0.6).powi(2)).sqrt();
+                (1.0 - e).max(0.0).sqrt()
+            }
+        };
+
+        // break up the perfect curves a bit, then sharpen the edges so the
+        // inside of a shape is sampled at full density and stays connected
+        smoothstep(EDGE_LOW, EDGE_HIGH, density * (0.8 + 0.4 * noise))
+    }
+}
+
+impl GalaxyShape {
+    /// The presets, for menus and batch tools.
+    pub const PRESETS: [GalaxyShape; 5] = [
+        GalaxyShape::Square,
+        GalaxyShape::Spiral { arms: 3 },
+        GalaxyShape::Ring,
+        GalaxyShape::Archipelago,
+        GalaxyShape::Elliptical,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GalaxyShape::Square => "square",
+            GalaxyShape::Spiral { .. } => "spiral",
+            GalaxyShape::Ring => "ring",
+            GalaxyShape::Archipelago => "archipelago",
+            GalaxyShape::Elliptical => "elliptical",
+        }
+    }
+
+    /// Looks up a preset by its `name`.
+    pub fn from_name(name: &str) -> Option<GalaxyShape> {
+        GalaxyShape::PRESETS
+            .into_iter()
+            .find(|shape| shape.name() == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::UNav;
+
+    #[test]
+    fn presets_generate_systems() {
+        for shape in GalaxyShape::PRESETS {
+            let unav = UNav::generate_shaped(19940131, shape, GalaxySize::Small);
+            assert!(unav.systems.len() > 10, "{} is too sparse", shape.name());
+        }
+    }
+
+    #[test]
+    fn ring_has_an_empty_centre() {
+        let unav = UNav::generate_shaped(19940131, GalaxyShape::Ring, GalaxySize::Medium);
+        let radius = GalaxySize::Medium.extent() as f64 / 2.0;
+        let inner = unav.systems.values().filter(|system| {
+            let p = system.position.as_dvec2() / radius;
+            p.length() < 0.2
+        });
+        assert_eq!(inner.count(), 0);
+    }
+}
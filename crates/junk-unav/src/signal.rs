@@ -0,0 +1,287 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::{CurrentSystem, Jumped, SystemId, UNav, UNavEntity, UNavToggle};
+
+/// Mixed into the run seed so signal noise has its own random stream.
+const SIGNAL_SEED_SALT: u64 = 0x7369_676e_616c;
+
+/// Half-angle of the bearing cone from a single scan, in radians.
+const BASE_SPREAD: f32 = 0.8;
+const MIN_SPREAD: f32 = 0.1;
+
+/// Relative error of the distance estimate from a single scan.
+const BASE_RANGE_NOISE: f32 = 0.4;
+
+/// Distance at which the signal has dropped to half strength.
+const SIGNAL_FALLOFF: f32 = 100.0;
+
+/// Points used to draw the arc of a bearing cone.
+const CONE_ARC_POINTS: usize = 12;
+
+const SIGNAL_COLOR: Color = Color::srgb(0.3, 0.9, 0.9);
+
+/// What a single scan says about where the signal comes from.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SignalReading {
+    pub from: SystemId,
+    pub origin: Vec2,
+    /// Direction to the source, radians counterclockwise from +x.
+    pub bearing: f32,
+    /// The source is within this many radians either side of `bearing`.
+    pub spread: f32,
+    /// 0.0 to 1.0, 1.0 being right on top of it.
+    pub strength: f32,
+    /// The source is between these distances from `origin`.
+    pub min_distance: f32,
+    pub max_distance: f32,
+}
+
+impl SignalReading {
+    pub fn contains(&self, point: Vec2) -> bool {
+        let offset = point - self.origin;
+        let distance = offset.length();
+        if distance < self.min_distance || distance > self.max_distance {
+            return false;
+        }
+        if self.spread >= PI || distance == 0.0 {
+            return true;
+        }
+        let angle = offset.y.atan2(offset.x) - self.bearing;
+        // wrap into -PI..PI before comparing
+        let angle = (angle + PI).rem_euclid(2.0 * PI) - PI;
+        angle.abs() <= self.spread
+    }
+}
+
+/// An anonymous signal coming from a hidden system, found by triangulating
+/// scans from different systems.
+#[derive(Resource, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Signal {
+    source: SystemId,
+    seed: u64,
+    pub readings: Vec<SignalReading>,
+}
+
+impl Signal {
+    pub fn new(source: SystemId, seed: u64) -> Self {
+        Self {
+            source,
+            seed,
+            readings: Vec::new(),
+        }
+    }
+
+    /// Takes a reading from `from`. Readings get more precise the more
+    /// systems the signal has been scanned from. Scanning from the same
+    /// system twice gives the same answer.
+    pub fn scan(&mut self, unav: &UNav, from: &SystemId) -> Option<&SignalReading> {
+        if let Some(i) = self.readings.iter().position(|r| r.from == *from) {
+            return Some(&self.readings[i]);
+        }
+        let origin_system = unav.get_system(from)?;
+        let source = unav.get_system(&self.source)?;
+        let origin = origin_system.position.as_vec2();
+        let offset = source.position.as_vec2() - origin;
+        let distance = offset.length();
+
+        let mut rng = StdRng::seed_from_u64(self.seed ^ SIGNAL_SEED_SALT ^ origin_system.seed);
+        let samples = (self.readings.len() + 1) as f32;
+        let precision = samples.sqrt();
+
+        let spread = if distance == 0.0 {
+            PI
+        } else {
+            (BASE_SPREAD / precision).max(MIN_SPREAD)
+        };
+        // the true bearing always stays inside the cone
+        let bearing = offset.y.atan2(offset.x) + rng.gen_range(-0.5..=0.5) * spread;
+
+        let noise = BASE_RANGE_NOISE / precision;
+        let estimate = distance * (1.0 + rng.gen_range(-noise..=noise));
+
+        self.readings.push(SignalReading {
+            from: from.clone(),
+            origin,
+            bearing,
+            spread,
+            strength: 1.0 / (1.0 + estimate / SIGNAL_FALLOFF),
+            min_distance: estimate / (1.0 + noise),
+            max_distance: estimate / (1.0 - noise),
+        });
+        self.readings.last()
+    }
+
+    /// Systems that fit every reading so far.
+    pub fn candidates(&self, unav: &UNav) -> Vec<SystemId> {
+        let mut candidates: Vec<SystemId> = unav
+            .systems
+            .values()
+            .filter(|system| {
+                let position = system.position.as_vec2();
+                self.readings
+                    .iter()
+                    .all(|reading| reading.contains(position))
+            })
+            .map(|system| system.id.clone())
+            .collect();
+        candidates.sort_by(|a, b| a.0.cmp(&b.0));
+        candidates
+    }
+}
+
+#[derive(Component)]
+pub(crate) struct SignalMarker;
+
+// the starting system gets a scan for free, after that every jump takes one
+pub(crate) fn scan_signal(
+    unav: Res<UNav>,
+    current_system: Res<CurrentSystem>,
+    mut jumps: EventReader<Jumped>,
+    mut signal: ResMut<Signal>,
+) {
+    if signal.readings.is_empty() {
+        signal.scan(&unav, &current_system.0);
+    }
+    for Jumped { to, .. } in jumps.read() {
+        signal.scan(&unav, to);
+    }
+}
+
+fn cone(reading: &SignalReading) -> shapes::Polygon {
+    let mut points = vec![Vec2::ZERO];
+    for i in 0..=CONE_ARC_POINTS {
+        let t = i as f32 / CONE_ARC_POINTS as f32;
+        let angle = reading.bearing - reading.spread + 2.0 * reading.spread * t;
+        points.push(Vec2::from_angle(angle) * reading.max_distance);
+    }
+    shapes::Polygon {
+        points,
+        closed: true,
+    }
+}
+
+/// Draws every reading as a cone toward the source with a ring at the
+/// estimated distance. Where they overlap is where to look.
+pub(crate) fn draw_signal(
+    mut commands: Commands,
+    signal: Res<Signal>,
+    unav_toggle: Res<UNavToggle>,
+    markers: Query<Entity, With<SignalMarker>>,
+) {
+    for marker in markers.iter() {
+        commands.entity(marker).despawn();
+    }
+
+    let visibility = if unav_toggle.active {
+        Visibility::Visible
+    } else {
+        Visibility::Hidden
+    };
+    for reading in signal.readings.iter() {
+        let transform = Transform::from_xyz(reading.origin.x, reading.origin.y, 0.75);
+        if reading.spread < PI {
+            commands.spawn((
+                ShapeBundle {
+                    path: GeometryBuilder::build_as(&cone(reading)),
+                    transform,
+                    visibility,
+                    ..default()
+                },
+                Fill::color(SIGNAL_COLOR.with_alpha(0.06)),
+                SignalMarker,
+                UNavEntity,
+            ));
+        }
+
+        let ring = shapes::Circle {
+            radius: (reading.min_distance + reading.max_distance) / 2.0,
+            center: Vec2::ZERO,
+        };
+        commands.spawn((
+            ShapeBundle {
+                path: GeometryBuilder::build_as(&ring),
+                transform,
+                visibility,
+                ..default()
+            },
+            Stroke::new(
+                SIGNAL_COLOR.with_alpha(0.15),
+                (reading.max_distance - reading.min_distance).max(1.0),
+            ),
+            SignalMarker,
+            UNavEntity,
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{unav::CONNECTION_DISTANCE, GoalKind};
+
+    fn signal_run(seed: u32) -> (UNav, Signal, SystemId) {
+        let unav = UNav::generate(seed);
+        let start = unav.get_most_central_system().id.clone();
+        let goal = crate::Goal::new(GoalKind::InvestigateSignal, &unav, &start).unwrap();
+        let signal = Signal::new(goal.target().unwrap().clone(), seed as u64);
+        (unav, signal, start)
+    }
+
+    #[test]
+    fn readings_narrow_down_the_source() {
+        let (unav, mut signal, start) = signal_run(19940131);
+        signal.scan(&unav, &start);
+        let first = signal.candidates(&unav);
+        assert!(first.contains(&signal.source));
+
+        for neighbour in unav
+            .connections(CONNECTION_DISTANCE)
+            .get_navigatable_systems(&start)
+        {
+            signal.scan(&unav, &neighbour);
+        }
+        let later = signal.candidates(&unav);
+        assert!(later.contains(&signal.source));
+        assert!(later.len() < first.len());
+    }
+
+    #[test]
+    fn readings_are_deterministic() {
+        let (unav, mut a, start) = signal_run(5);
+        let (_, mut b, _) = signal_run(5);
+        assert_eq!(a.scan(&unav, &start), b.scan(&unav, &start));
+    }
+
+    #[test]
+    fn every_jump_takes_a_reading() {
+        let (unav, signal, start) = signal_run(19940131);
+        let neighbour = unav
+            .connections(CONNECTION_DISTANCE)
+            .get_navigatable_systems(&start)[0]
+            .clone();
+        let mut app = App::new();
+        app.insert_resource(unav)
+            .insert_resource(CurrentSystem(start.clone()))
+            .insert_resource(signal)
+            .init_resource::<crate::Fuel>()
+            .add_event::<crate::JumpTo>()
+            .add_event::<Jumped>()
+            .add_systems(Update, (crate::jump::jump, scan_signal).chain());
+
+        app.update();
+        let readings = &app.world().resource::<Signal>().readings;
+        assert_eq!(readings.len(), 1);
+        assert_eq!(readings[0].from, start);
+
+        app.world_mut().send_event(crate::JumpTo(neighbour.clone()));
+        app.update();
+        let readings = &app.world().resource::<Signal>().readings;
+        assert_eq!(readings.len(), 2);
+        assert_eq!(readings[1].from, neighbour);
+    }
+}
@@ -0,0 +1,334 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    fmt::Display,
+};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    shape::{GalaxyShape, GalaxySize},
+    unav::CONNECTION_DISTANCE,
+    SystemComponents, SystemId, UNav,
+};
+
+/// Numbers describing how a generated map turned out.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GalaxyStats {
+    pub system_count: usize,
+    /// Number of systems with each connection count.
+    pub degree_distribution: BTreeMap<usize, usize>,
+    /// Connections of the starting system.
+    pub start_degree: usize,
+    /// Longest shortest path, in jumps, within any connected component.
+    /// Estimated with a double sweep, which is exact on tree-like maps and
+    /// can come in a little short on others.
+    pub diameter: usize,
+    pub component_count: usize,
+    /// Systems reachable from the start, the start included.
+    pub reachable: usize,
+    /// Jumps from the start to the farthest system reachable from it.
+    pub reach: usize,
+    /// Number of systems containing each kind of component.
+    pub components: HashMap<SystemComponents, usize>,
+}
+
+/// Jumps from `start` to every system reachable from it.
+pub(crate) fn jumps_from(
+    start: &SystemId,
+    adjacency: &HashMap<SystemId, Vec<SystemId>>,
+) -> HashMap<SystemId, usize> {
+    let mut jumps = HashMap::from([(start.clone(), 0)]);
+    let mut queue = VecDeque::from([start.clone()]);
+    while let Some(current) = queue.pop_front() {
+        let next = jumps[&current] + 1;
+        for neighbour in adjacency.get(&current).into_iter().flatten() {
+            if !jumps.contains_key(neighbour) {
+                jumps.insert(neighbour.clone(), next);
+                queue.push_back(neighbour.clone());
+            }
+        }
+    }
+    jumps
+}
+
+/// The system most jumps away, ties going to the lowest id.
+fn farthest(jumps: &HashMap<SystemId, usize>) -> Option<&SystemId> {
+    jumps
+        .iter()
+        .max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0 .0.cmp(&a.0 .0)))
+        .map(|(id, _)| id)
+}
+
+impl UNav {
+    pub fn stats(&self) -> GalaxyStats {
+        let adjacency = self.connections(CONNECTION_DISTANCE).adjacency();
+        let degree = |id: &SystemId| adjacency.get(id).map_or(0, Vec::len);
+
+        let mut degree_distribution = BTreeMap::new();
+        let mut components = HashMap::new();
+        for system in self.systems.values() {
+            *degree_distribution.entry(degree(&system.id)).or_default() += 1;
+            for component in system.properties.r#type.iter() {
+                *components.entry(component.clone()).or_default() += 1;
+            }
+        }
+
+        let start = match self.systems.is_empty() {
+            true => None,
+            false => Some(self.get_most_central_system().id.clone()),
+        };
+        // sorted, so the diameter estimate doesn't depend on hash order
+        let mut ids: Vec<&SystemId> = self.systems.keys().collect();
+        ids.sort_by(|a, b| a.0.cmp(&b.0));
+
+        // two searches per connected component: the first finds its systems
+        // and the one farthest from where it began, the second measures the
+        // longest path from there
+        let mut diameter = 0;
+        let mut seen = HashSet::new();
+        let mut component_count = 0;
+        let mut start_jumps = HashMap::new();
+        for id in start.iter().chain(ids) {
+            if seen.contains(id) {
+                continue;
+            }
+            component_count += 1;
+            let jumps = jumps_from(id, &adjacency);
+            let farthest = farthest(&jumps).unwrap_or(id);
+            diameter = diameter.max(
+                jumps_from(farthest, &adjacency)
+                    .into_values()
+                    .max()
+                    .unwrap_or(0),
+            );
+            seen.extend(jumps.keys().cloned());
+            if Some(id) == start.as_ref() {
+                start_jumps = jumps;
+            }
+        }
+
+        let (start_degree, reachable, reach) = match &start {
+            None => (0, 0, 0),
+            Some(start) => {
+                let reach = start_jumps.values().copied().max().unwrap_or(0);
+                // the estimate can't be shorter than a path actually found
+                diameter = diameter.max(reach);
+                (degree(start), start_jumps.len(), reach)
+            }
+        };
+
+        GalaxyStats {
+            system_count: self.systems.len(),
+            degree_distribution,
+            start_degree,
+            diameter,
+            component_count,
+            reachable,
+            reach,
+            components,
+        }
+    }
+}
+
+impl Display for GalaxyStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Systems: {}", self.system_count)?;
+        writeln!(
+            f,
+            "Reachable from start: {} in {} jumps",
+            self.reachable, self.reach
+        )?;
+        writeln!(f, "Start connections: {}", self.start_degree)?;
+        writeln!(f, "Diameter: {} jumps", self.diameter)?;
+        writeln!(f, "Components: {}", self.component_count)?;
+        let degrees = self
+            .degree_distribution
+            .iter()
+            .map(|(degree, count)| format!("{}:{}", degree, count))
+            .collect::<Vec<_>>()
+            .join(" ");
+        writeln!(f, "Degrees: {}", degrees)?;
+        let mut components = self
+            .components
+            .iter()
+            .map(|(component, count)| format!("{:?}:{}", component, count))
+            .collect::<Vec<_>>();
+        components.sort();
+        write!(f, "Contents: {}", components.join(" "))
+    }
+}
+
+/// Limits a generated map has to meet to be worth playing.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GalaxyThresholds {
+    pub min_systems: usize,
+    /// Minimum share of systems reachable from the start, 0.0 to 1.0.
+    pub min_reachable: f64,
+    /// Minimum jumps from the start to the farthest reachable system, so
+    /// there's somewhere to go.
+    pub min_reach: usize,
+    /// Minimum connections of the starting system.
+    pub min_start_degree: usize,
+    /// Maximum connections of the starting system, to catch clumped starts.
+    pub max_start_degree: usize,
+    pub max_components: usize,
+}
+
+impl Default for GalaxyThresholds {
+    fn default() -> Self {
+        Self {
+            min_systems: 30,
+            min_reachable: 0.9,
+            min_reach: 6,
+            min_start_degree: 2,
+            max_start_degree: 8,
+            max_components: 3,
+        }
+    }
+}
+
+/// Mixed into a rejected seed to pick the next one to try.
+const REROLL_SEED_SALT: u64 = 0x7265_726f_6c6c;
+
+impl GalaxyThresholds {
+    /// Reasons the stats fail these thresholds, empty if they pass.
+    pub fn failures(&self, stats: &GalaxyStats) -> Vec<String> {
+        let mut failures = Vec::new();
+        if stats.system_count < self.min_systems {
+            failures.push(format!(
+                "{} systems, need {}",
+                stats.system_count, self.min_systems
+            ));
+        }
+        let reachable = stats.reachable as f64 / stats.system_count.max(1) as f64;
+        if reachable < self.min_reachable {
+            failures.push(format!(
+                "{:.0}% reachable, need {:.0}%",
+                reachable * 100.0,
+                self.min_reachable * 100.0
+            ));
+        }
+        if stats.reach < self.min_reach {
+            failures.push(format!(
+                "reach of {} jumps, need {}",
+                stats.reach, self.min_reach
+            ));
+        }
+        if stats.start_degree < self.min_start_degree {
+            failures.push(format!(
+                "start has {} connections, need {}",
+                stats.start_degree, self.min_start_degree
+            ));
+        }
+        if stats.start_degree > self.max_start_degree {
+            failures.push(format!(
+                "start has {} connections, allowed {}",
+                stats.start_degree, self.max_start_degree
+            ));
+        }
+        if stats.component_count > self.max_components {
+            failures.push(format!(
+                "{} components, allowed {}",
+                stats.component_count, self.max_components
+            ));
+        }
+        failures
+    }
+
+    pub fn accepts(&self, stats: &GalaxyStats) -> bool {
+        self.failures(stats).is_empty()
+    }
+
+    /// Generates from `seed`, rerolling rejected maps with seeds derived from
+    /// it. Returns the first accepted map, or `None` after `max_attempts`.
+    pub fn generate(
+        &self,
+        seed: u32,
+        shape: GalaxyShape,
+        size: GalaxySize,
+        max_attempts: usize,
+    ) -> Option<UNav> {
+        if max_attempts == 0 {
+            return None;
+        }
+        let unav = UNav::generate_shaped(seed, shape, size);
+        if self.accepts(&unav.stats()) {
+            return Some(unav);
+        }
+        self.reroll(seed, shape, size, max_attempts - 1)
+    }
+
+    /// The seeds `generate` goes on to once `seed` itself is rejected, for
+    /// callers that already generated and rejected it.
+    pub fn reroll(
+        &self,
+        seed: u32,
+        shape: GalaxyShape,
+        size: GalaxySize,
+        rerolls: usize,
+    ) -> Option<UNav> {
+        let mut rng = StdRng::seed_from_u64(seed as u64 ^ REROLL_SEED_SALT);
+        (0..rerolls).find_map(|_| {
+            let unav = UNav::generate_shaped(rng.gen(), shape, size);
+            self.accepts(&unav.stats()).then_some(unav)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats_add_up() {
+        let unav = UNav::generate(19940131);
+        let stats = unav.stats();
+        assert_eq!(stats.system_count, unav.systems.len());
+        assert_eq!(
+            stats.degree_distribution.values().sum::<usize>(),
+            stats.system_count
+        );
+        assert!(stats.reachable <= stats.system_count);
+        assert!(stats.reach <= stats.diameter);
+        assert!(stats.component_count >= 1);
+        assert_eq!(unav.stats(), stats);
+    }
+
+    #[test]
+    fn double_sweep_finds_path_ends() {
+        let id = |name: &str| SystemId(name.to_string());
+        // a-b-c-d with e hanging off b: the far ends are a or e and d
+        let adjacency = HashMap::from([
+            (id("a"), vec![id("b")]),
+            (id("b"), vec![id("a"), id("c"), id("e")]),
+            (id("c"), vec![id("b"), id("d")]),
+            (id("d"), vec![id("c")]),
+            (id("e"), vec![id("b")]),
+        ]);
+        let far = farthest(&jumps_from(&id("c"), &adjacency)).unwrap().clone();
+        assert_eq!(far, id("a"));
+        let diameter = jumps_from(&far, &adjacency).into_values().max();
+        assert_eq!(diameter, Some(3));
+    }
+
+    #[test]
+    fn rerolls_until_accepted() {
+        let thresholds = GalaxyThresholds::default();
+        let unav = thresholds
+            .generate(19940131, GalaxyShape::Archipelago, GalaxySize::Small, 50)
+            .unwrap();
+        assert!(thresholds.accepts(&unav.stats()));
+        let rerolled = thresholds
+            .reroll(19940131, GalaxyShape::Archipelago, GalaxySize::Small, 49)
+            .map(|unav| unav.seed);
+        assert!(unav.seed == 19940131 || rerolled == Some(unav.seed));
+
+        let impossible = GalaxyThresholds {
+            min_systems: usize::MAX,
+            ..GalaxyThresholds::default()
+        };
+        assert!(impossible
+            .generate(1, GalaxyShape::Square, GalaxySize::Small, 3)
+            .is_none());
+    }
+}
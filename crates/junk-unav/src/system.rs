@@ -1,12 +1,16 @@
 use std::{collections::HashSet, fmt::Display};
 
-use bevy::math::I64Vec2;
-use rand::{rngs::StdRng, Rng, SeedableRng};
+use bevy_math::I64Vec2;
+use rand::{distributions::WeightedIndex, prelude::Distribution, rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::{contents::SystemContents, discovery::Knowledge, faction::Faction, hazard::Hazards};
 
 const SYSTEM_ID_ALPHABET: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
 const SYSTEM_ID_LENGTH: usize = 10;
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
 pub struct SystemId(pub String);
 
 impl SystemId {
@@ -21,8 +25,14 @@ impl SystemId {
     }
 }
 
-#[derive(Clone, Debug)]
-pub struct SystemName(pub String);
+/// A system's common name plus its catalogue designation, e.g. `QX-417`.
+/// The common name starts out as the designation until `UNav::assign_names`
+/// gives it a proper one.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SystemName {
+    pub name: String,
+    pub designation: String,
+}
 
 impl SystemName {
     pub fn generate(rng: &mut StdRng) -> Self {
@@ -41,34 +51,81 @@ impl SystemName {
             .map(|_| rng.gen_range(0..10).to_string())
             .collect();
 
-        let name = format!("{}-{}", letters, digits);
-        Self(name)
+        let designation = format!("{}-{}", letters, digits);
+        Self {
+            name: designation.clone(),
+            designation,
+        }
+    }
+}
+
+impl Display for SystemName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.name == self.designation {
+            write!(f, "{}", self.designation)
+        } else {
+            write!(f, "{} ({})", self.name, self.designation)
+        }
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct System {
     pub id: SystemId,
     pub position: I64Vec2,
     pub seed: u64,
     pub properties: SystemProperties,
+    pub contents: SystemContents,
+    pub knowledge: Knowledge,
+    pub owner: Option<Faction>,
 }
 
 impl System {
-    pub fn new(seed: u64, position: I64Vec2) -> Self {
+    /// `danger` is 0.0 at the starting system and 1.0 at the far edge of the map.
+    pub fn new(seed: u64, position: I64Vec2, danger: f64) -> Self {
         let mut rng = StdRng::seed_from_u64(seed);
         let id = SystemId::generate(&mut rng);
-        let properties = SystemProperties::from_rngs(&mut rng);
+        let properties = SystemProperties::from_rngs(&mut rng, danger);
+        let contents = SystemContents::generate(seed, &properties);
         Self {
             id,
             position,
             seed,
             properties,
+            contents,
+            knowledge: Knowledge::Unknown,
+            owner: None,
+        }
+    }
+
+    /// Manhattan distance between two systems, the metric used for connections.
+    pub fn distance_to(&self, other: &System) -> usize {
+        ((self.position.x - other.position.x).abs() + (self.position.y - other.position.y).abs())
+            as usize
+    }
+
+    /// Hands the system to a faction, stations included. Left unclaimed,
+    /// its stations go back to their founders.
+    pub fn set_owner(&mut self, owner: Option<Faction>) {
+        self.owner = owner;
+        // stations belong to whoever holds the system
+        for station in self.contents.stations.iter_mut() {
+            station.faction = owner.unwrap_or(station.founder);
+        }
+    }
+
+    /// What the player can see of this system given its current knowledge.
+    pub fn report(&self) -> SystemReport<'_> {
+        SystemReport {
+            properties: &self.properties,
+            contents: &self.contents,
+            owner: self.owner,
+            knowledge: self.knowledge,
         }
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum SystemComponents {
     Planet,
     Station,
@@ -78,22 +135,26 @@ pub enum SystemComponents {
     Anomaly,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct SystemProperties {
     pub name: SystemName,
     pub r#type: HashSet<SystemComponents>,
     pub temperature: f64,
+    pub danger: f64,
+    pub hazards: Hazards,
 }
 
 impl Display for SystemProperties {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "System: {}\nTemperature: {:.2}\nDetected:{}",
-            self.name.0,
+            "System: {}\nTemperature: {:.2}\nDanger: {}/5\nHazards: {}\nDetected:{}",
+            self.name,
             self.temperature,
+            self.danger_level(),
+            self.hazards,
             if self.r#type.is_empty() {
-                " ?".to_string()
+                " None".to_string()
             } else {
                 format!(
                     "\n{}",
@@ -108,28 +169,78 @@ impl Display for SystemProperties {
     }
 }
 
+/// Display wrapper that only reveals what the player knows about a system.
+pub struct SystemReport<'a> {
+    pub properties: &'a SystemProperties,
+    pub contents: &'a SystemContents,
+    pub owner: Option<Faction>,
+    pub knowledge: Knowledge,
+}
+
+impl Display for SystemReport<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.knowledge {
+            Knowledge::Unknown => write!(f, "System: ?"),
+            Knowledge::Detected => write!(f, "System: ?\nTemperature: ?\nDetected: ?"),
+            Knowledge::Scanned | Knowledge::Visited => {
+                write!(f, "{}\n{}", self.properties, self.contents)?;
+                match self.owner {
+                    Some(owner) => write!(f, "\nTerritory: {:?}", owner)?,
+                    None => write!(f, "\nTerritory: Unclaimed")?,
+                }
+                if self.knowledge == Knowledge::Visited {
+                    write!(f, "\nVisited")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
 impl SystemProperties {
-    pub fn from_rngs(rng: &mut StdRng) -> Self {
+    pub fn from_rngs(rng: &mut StdRng, danger: f64) -> Self {
         let name = SystemName::generate(rng);
 
+        // stations thin out and pirates and anomalies take over the further
+        // a system is from the start
+        let components = [
+            (SystemComponents::Planet, 1.0),
+            (SystemComponents::Station, 1.6 - 1.4 * danger),
+            (SystemComponents::Asteroid, 1.0),
+            (SystemComponents::Wreckage, 0.6 + 0.8 * danger),
+            (SystemComponents::Pirates, 0.1 + 1.9 * danger),
+            (SystemComponents::Anomaly, 0.2 + 1.3 * danger),
+        ];
+        let weights = WeightedIndex::new(components.iter().map(|(_, weight)| *weight)).unwrap();
+
         let mut r#type = HashSet::new();
         let attempts = rng.gen_range(0..4);
         for _ in 0..attempts {
-            let component = match rng.gen_range(0..6) {
-                0 => SystemComponents::Planet,
-                1 => SystemComponents::Station,
-                2 => SystemComponents::Asteroid,
-                3 => SystemComponents::Wreckage,
-                4 => SystemComponents::Pirates,
-                _ => SystemComponents::Anomaly,
-            };
-            r#type.insert(component);
+            let (component, _) = &components[weights.sample(rng)];
+            r#type.insert(component.clone());
         }
 
         Self {
             name,
             r#type,
             temperature: rng.gen_range(0.0..1.0),
+            danger,
+            hazards: Hazards::default(),
         }
     }
+
+    /// Danger as a 1 to 5 rating for display.
+    pub fn danger_level(&self) -> u8 {
+        1 + (self.danger.clamp(0.0, 1.0) * 4.0).round() as u8
+    }
+
+    /// Multiplier for loot and resource rewards, dangerous systems pay more.
+    pub fn reward_multiplier(&self) -> f64 {
+        1.0 + self.danger * 2.0
+    }
+
+    /// How strong ships met in this system should be, 0.0 to 1.0.
+    pub fn ship_strength(&self) -> f32 {
+        (0.2 + self.danger * 0.8) as f32
+    }
 }
@@ -1,17 +1,30 @@
-use crate::{System, SystemId};
+use crate::{
+    hazard::HazardField,
+    save::ChunkDeltas,
+    shape::{GalaxyDensity, GalaxyShape, GalaxySize},
+    System, SystemId,
+};
 use bevy_ecs::system::Resource;
+use bevy_math::{I64Vec2, Rect};
+use junk_procgen::PoissonSampler;
 use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-const X_MAX: isize = 256;
-const Y_MAX: isize = 256;
+/// Closest two systems get.
+const SYSTEM_SPACING: f32 = 20.0;
 
+/// Systems closer than this (Manhattan distance) are connected by a jump lane.
+pub const CONNECTION_DISTANCE: usize = 35;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Connection {
     pub from: SystemId,
     pub to: SystemId,
     pub distance: usize,
 }
 
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Connections {
     pub connections: Vec<Connection>,
 }
@@ -38,25 +51,84 @@ impl Connections {
     pub fn connections(&self) -> &Vec<Connection> {
         &self.connections
     }
+
+    /// Neighbour lists for every system that has at least one connection.
+    pub fn adjacency(&self) -> HashMap<SystemId, Vec<SystemId>> {
+        let mut adjacency: HashMap<SystemId, Vec<SystemId>> = HashMap::new();
+        for connection in self.connections.iter() {
+            adjacency
+                .entry(connection.from.clone())
+                .or_default()
+                .push(connection.to.clone());
+        }
+        adjacency
+    }
 }
 
-#[derive(Resource, Clone)]
+#[derive(Resource, Clone, Serialize, Deserialize)]
 pub struct UNav {
+    /// What the map was generated from, enough to regenerate it.
+    pub seed: u32,
+    pub shape: GalaxyShape,
+    pub size: GalaxySize,
     pub systems: HashMap<SystemId, System>,
+    pub hazards: HazardField,
+    /// What the player changed in system scenes, see `ChunkDeltas`.
+    #[serde(default)]
+    pub chunks: ChunkDeltas,
 }
 
 impl UNav {
     pub fn generate(random_seed: u32) -> UNav {
+        UNav::generate_shaped(random_seed, GalaxyShape::default(), GalaxySize::default())
+    }
+
+    pub fn generate_shaped(random_seed: u32, shape: GalaxyShape, size: GalaxySize) -> UNav {
         let mut system_seed = rand::rngs::StdRng::seed_from_u64(random_seed as u64);
-        let positions = crate::poisson::sample(X_MAX, Y_MAX, 20.0, 30, random_seed as u64);
+        let extent = size.extent();
+        // sampled over 0..extent and then centred, as galaxies always were,
+        // so a seed keeps giving the same map
+        let bounds = Rect::new(0.0, 0.0, extent as f32, extent as f32);
+        let centre = I64Vec2::splat((extent / 2) as i64);
+        let sampler = PoissonSampler::new(bounds, SYSTEM_SPACING).snapped();
+        let points = match shape {
+            GalaxyShape::Square => sampler.sample(random_seed as u64),
+            _ => {
+                let density = GalaxyDensity::new(shape, size, random_seed);
+                sampler
+                    .thinned(move |point| density.at(point.as_i64vec2() - centre))
+                    .refilling()
+                    .sample(random_seed as u64)
+            }
+        };
+        let positions: Vec<I64Vec2> = points
+            .into_iter()
+            .map(|point| point.as_i64vec2() - centre)
+            .collect();
+        let dangers = crate::danger::danger_levels(&positions);
         let systems = positions
             .into_iter()
-            .map(|point| {
-                let system = crate::system::System::new(system_seed.gen(), point);
+            .zip(dangers)
+            .map(|(point, danger)| {
+                let system = crate::system::System::new(system_seed.gen(), point, danger);
                 (system.id.clone(), system)
             })
             .collect();
-        UNav { systems }
+        let hazards = HazardField::new(random_seed);
+        let mut unav = UNav {
+            seed: random_seed,
+            shape,
+            size,
+            systems,
+            hazards,
+            chunks: ChunkDeltas::default(),
+        };
+        for system in unav.systems.values_mut() {
+            system.properties.hazards = unav.hazards.at(system.position.as_dvec2());
+        }
+        unav.assign_territories(random_seed as u64);
+        unav.assign_names();
+        unav
     }
 
     pub fn connections(&self, distance_filter: usize) -> Connections {
@@ -64,9 +136,7 @@ impl UNav {
         for (from_id, from_node) in self.systems.iter() {
             for (to_id, to_node) in self.systems.iter() {
                 if from_id != to_id {
-                    let distance = ((from_node.position.x - to_node.position.x).abs()
-                        + (from_node.position.y - to_node.position.y).abs())
-                        as usize;
+                    let distance = from_node.distance_to(to_node);
                     if distance <= distance_filter {
                         connections.push(Connection {
                             from: from_id.clone(),
@@ -87,7 +157,56 @@ impl UNav {
     pub fn get_most_central_system(&self) -> &System {
         self.systems
             .values()
-            .min_by_key(|system| system.position.x.abs() + system.position.y.abs())
+            .min_by_key(|system| crate::danger::centrality_key(system.position))
             .unwrap()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Where seed 19940131 put its systems when galaxies were sampled with
+    /// the integer Poisson sampler, before `PoissonSampler` replaced it.
+    /// Sorted, as x, y pairs.
+    const SQUARE_LAYOUT: [i64; 222] = [
+        -128, 35, -128, 126, -125, -116, -119, -84, -118, -60, -118, -37, -117, 106, -113, -1,
+        -113, 53, -111, 78, -107, 127, -106, 23, -101, -115, -100, -71, -98, 109, -96, -46, -96,
+        -26, -94, 7, -91, 58, -87, 126, -86, -99, -85, 82, -82, 29, -79, -14, -77, -40, -76, -79,
+        -75, 105, -73, 8, -70, -119, -67, 126, -64, 46, -62, 26, -60, -27, -59, -50, -59, 80, -56,
+        -87, -52, 111, -44, -104, -44, 50, -43, -2, -39, 126, -35, -22, -33, -122, -32, -71, -31,
+        31, -28, 95, -26, 68, -25, -45, -21, -97, -17, 50, -13, -125, -13, -2, -11, 21, -7, -73,
+        -3, 77, -3, 125, -2, 103, -1, -104, 1, -50, 4, -26, 10, 60, 12, 6, 15, 27, 19, -73, 24,
+        122, 25, -31, 27, -104, 27, 100, 28, -127, 28, -51, 28, 51, 29, 76, 38, 13, 39, -9, 42,
+        -76, 47, 43, 53, -24, 54, 79, 56, 114, 57, -61, 58, -118, 64, 58, 67, -87, 67, 24, 73, -3,
+        76, 91, 76, 123, 78, -31, 79, -123, 82, -52, 84, 14, 85, 42, 87, -74, 91, 61, 94, -14, 96,
+        82, 97, -102, 97, 119, 107, 5, 109, 29, 111, -68, 112, 96, 114, -34, 115, 60, 117, -125,
+        123, -103, 125, -11, 126, -54, 126, 43, 127, 18, 127, 113,
+    ];
+
+    /// The same seed as a two-armed spiral.
+    const SPIRAL_LAYOUT: [i64; 60] = [
+        -104, -5, -88, -36, -87, 19, -82, -8, -79, 40, -64, 25, -63, 60, -45, 43, -36, 73, -27, 51,
+        -24, -49, -20, -23, -19, 14, -15, 70, -10, 39, 1, -14, 5, -35, 9, -63, 9, 66, 10, 12, 11,
+        40, 25, 53, 26, -7, 33, 24, 35, -41, 46, -58, 59, -31, 66, -64, 80, -46, 88, -22,
+    ];
+
+    /// System positions in order, flattened to x, y pairs.
+    fn layout(unav: &UNav) -> Vec<i64> {
+        let mut positions: Vec<(i64, i64)> = unav
+            .systems
+            .values()
+            .map(|system| (system.position.x, system.position.y))
+            .collect();
+        positions.sort();
+        positions.into_iter().flat_map(|(x, y)| [x, y]).collect()
+    }
+
+    #[test]
+    fn seeds_keep_their_galaxy_layout() {
+        assert_eq!(layout(&UNav::generate(19940131)), SQUARE_LAYOUT);
+        let spiral =
+            UNav::generate_shaped(19940131, GalaxyShape::Spiral { arms: 2 }, GalaxySize::Small);
+        assert_eq!(layout(&spiral), SPIRAL_LAYOUT);
+    }
+}
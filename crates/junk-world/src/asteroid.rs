@@ -0,0 +1,623 @@
+use std::f32::consts::{PI, TAU};
+
+use bevy::{
+    asset::{Assets, Handle, RenderAssetUsages},
+    color::{Color, ColorToComponents, LinearRgba},
+    math::{IVec2, Quat, Rect, Vec2},
+    prelude::{
+        ChildBuild, ChildBuilder, Commands, Component, DespawnRecursiveExt, Entity, Event,
+        EventReader, EventWriter, Mesh, Mesh2d, Query, Res, ResMut, Resource, Time, Transform,
+        With, Without,
+    },
+    render::mesh::{Indices, PrimitiveTopology},
+    sprite::{ColorMaterial, MeshMaterial2d},
+};
+use junk_procgen::PoissonSampler;
+use junk_unav::{AsteroidBelt, ChunkDelta, ChunkDeltas, ResourceKind, ResourceYield};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    cargo::Cargo,
+    chunk::{chunk_seed, ChunkItem, OrbitingChunk, CHUNK_SIZE},
+};
+
+/// World units per in-system orbit unit.
+const ORBIT_SCALE: f32 = 2.0;
+
+/// How wide a belt is, in world units.
+const BELT_WIDTH: f32 = 200.0;
+
+/// Spacing between rocks in the sparsest and the densest belts.
+const SPARSE_SPACING: f32 = 160.0;
+const DENSE_SPACING: f32 = 50.0;
+
+const ROCK_MIN_RADIUS: f32 = 6.0;
+const ROCK_MAX_RADIUS: f32 = 24.0;
+
+/// Speed the belts turn around the star at, in radians per second. The
+/// belts turn as one, so rocks keep their places in them.
+const ORBIT_SPEED: f32 = 0.01;
+
+/// Mixed into the system seed so rocks don't line up with the stars.
+const ASTEROID_SEED_SALT: u64 = 0x726f_636b;
+
+/// The asteroid belts of the system the player is in, as generated. The
+/// game fills this in on arrival, rocks are generated from it and what has
+/// been mined since comes off them through `ChunkDeltas`.
+#[derive(Resource, Clone, Debug, Default, PartialEq)]
+pub struct SystemAsteroids {
+    pub seed: u64,
+    pub belts: Vec<AsteroidBelt>,
+}
+
+/// A mineable rock. Its yields are its share of the belt's deposits.
+#[derive(Component, Clone, Debug, PartialEq)]
+pub struct Asteroid {
+    /// Index of the belt in the system's contents.
+    pub belt: usize,
+    pub radius: f32,
+    pub yields: Vec<ResourceYield>,
+}
+
+impl Asteroid {
+    /// Takes up to `amount` of a resource out of the rock, returning how much
+    /// was actually mined.
+    pub fn mine(&mut self, resource: ResourceKind, amount: u32) -> u32 {
+        let Some(deposit) = self.yields.iter_mut().find(|y| y.resource == resource) else {
+            return 0;
+        };
+        let mined = amount.min(deposit.amount);
+        deposit.amount -= mined;
+        mined
+    }
+
+    pub fn is_depleted(&self) -> bool {
+        self.yields.iter().all(|y| y.amount == 0)
+    }
+}
+
+/// Collision outline in local space, with a bounding radius for cheap
+/// rejection.
+#[derive(Component, Clone, Debug, PartialEq)]
+pub struct Collider {
+    pub radius: f32,
+    pub polygon: Vec<Vec2>,
+}
+
+impl Collider {
+    /// Whether a world-space point is inside the outline.
+    pub fn contains(&self, transform: &Transform, point: Vec2) -> bool {
+        let local = transform.rotation.inverse() * (point.extend(0.0) - transform.translation)
+            / transform.scale;
+        let local = local.truncate();
+        if local.length() > self.radius {
+            return false;
+        }
+
+        // even-odd rule
+        let mut inside = false;
+        let mut previous = self.polygon[self.polygon.len() - 1];
+        for &current in &self.polygon {
+            if (current.y > local.y) != (previous.y > local.y)
+                && local.x
+                    < (previous.x - current.x) * (local.y - current.y) / (previous.y - current.y)
+                        + current.x
+            {
+                inside = !inside;
+            }
+            previous = current;
+        }
+        inside
+    }
+
+    /// Rough overlap test between two colliders, using their bounding
+    /// circles.
+    pub fn overlaps(
+        &self,
+        transform: &Transform,
+        other: &Collider,
+        other_transform: &Transform,
+    ) -> bool {
+        let distance = transform
+            .translation
+            .truncate()
+            .distance(other_transform.translation.truncate());
+        distance < self.radius + other.radius
+    }
+}
+
+/// Slow tumbling motion, on top of the belt turning around the star.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct Drift {
+    /// Radians per second.
+    pub spin: f32,
+}
+
+/// How far the belts have turned around the star, in radians. Rocks are
+/// generated in the belts' own frame and turned into place by this.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq)]
+pub struct BeltAngle(pub f32);
+
+impl BeltAngle {
+    /// A position in the belts' frame, turned to where it is now.
+    pub fn to_world(&self, position: Vec2) -> Vec2 {
+        Vec2::from_angle(self.0).rotate(position)
+    }
+
+    /// A world position in the belts' frame.
+    pub fn to_belt(&self, position: Vec2) -> Vec2 {
+        Vec2::from_angle(-self.0).rotate(position)
+    }
+
+    pub fn rotation(&self) -> Quat {
+        Quat::from_rotation_z(self.0)
+    }
+}
+
+/// Asks for `amount` of a resource to be mined out of a rock.
+#[derive(Event, Clone, Debug)]
+pub struct MineAsteroid {
+    pub asteroid: Entity,
+    pub resource: ResourceKind,
+    pub amount: u32,
+}
+
+/// Sent for every rock mined, so the belt it belongs to can be lowered to
+/// match.
+#[derive(Event, Clone, Debug, PartialEq)]
+pub struct AsteroidMined {
+    /// Index of the belt in the system's contents.
+    pub belt: usize,
+    pub resource: ResourceKind,
+    pub amount: u32,
+}
+
+/// Everything needed to spawn one rock.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rock {
+    pub position: Vec2,
+    pub outline: Vec<Vec2>,
+    pub asteroid: Asteroid,
+    pub drift: Drift,
+}
+
+impl Rock {
+    pub fn collider(&self) -> Collider {
+        Collider {
+            radius: self.asteroid.radius,
+            polygon: self.outline.clone(),
+        }
+    }
+
+    /// Colour of the resource the rock holds the most of.
+    pub fn color(&self) -> Color {
+        let richest = self
+            .asteroid
+            .yields
+            .iter()
+            .max_by_key(|y| y.amount)
+            .map(|y| y.resource);
+        match richest {
+            Some(ResourceKind::Iron) => Color::srgb(0.55, 0.35, 0.25),
+            Some(ResourceKind::Nickel) => Color::srgb(0.5, 0.5, 0.48),
+            Some(ResourceKind::Ice) => Color::srgb(0.7, 0.85, 0.95),
+            Some(ResourceKind::Platinum) => Color::srgb(0.85, 0.85, 0.8),
+            Some(ResourceKind::Helium3) => Color::srgb(0.75, 0.7, 0.45),
+            None => Color::srgb(0.4, 0.4, 0.4),
+        }
+    }
+
+    /// A triangle fan around the rock's centre.
+    pub fn mesh(&self) -> Mesh {
+        let color = LinearRgba::from(self.color()).to_f32_array();
+        let mut positions = vec![[0.0, 0.0, 0.0]];
+        positions.extend(self.outline.iter().map(|p| [p.x, p.y, 0.0]));
+        let colors = vec![color; positions.len()];
+        let n = self.outline.len() as u32;
+        let indices = (0..n).flat_map(|i| [0, i + 1, (i + 1) % n + 1]).collect();
+
+        Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::RENDER_WORLD,
+        )
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, colors)
+        .with_inserted_indices(Indices::U32(indices))
+    }
+}
+
+/// Irregular outline: points around a circle pushed in by varying amounts.
+fn outline(rng: &mut StdRng, radius: f32) -> Vec<Vec2> {
+    let sides = rng.gen_range(7..=12);
+    (0..sides)
+        .map(|i| {
+            let jitter = rng.gen_range(-0.3..0.3);
+            let angle = (i as f32 + jitter) / sides as f32 * TAU;
+            Vec2::from_angle(angle) * radius * rng.gen_range(0.65..1.0)
+        })
+        .collect()
+}
+
+/// Rough share of a Poisson-sampled area that one rock takes, in units of
+/// spacing squared. Used to spread a belt's deposits over rocks that are
+/// generated a chunk at a time.
+const ROCKS_PER_SPACING_SQUARED: f32 = 0.7;
+
+fn belt_radii(belt: &AsteroidBelt) -> (f32, f32) {
+    let orbit = belt.orbit * ORBIT_SCALE;
+    (orbit - BELT_WIDTH / 2.0, orbit + BELT_WIDTH / 2.0)
+}
+
+fn belt_spacing(belt: &AsteroidBelt) -> f32 {
+    SPARSE_SPACING + (DENSE_SPACING - SPARSE_SPACING) * belt.density
+}
+
+/// Expected sum of squared radii over every rock in the belt, what each
+/// rock's share of the deposits is measured against.
+fn expected_rock_area(belt: &AsteroidBelt) -> f32 {
+    let (inner, outer) = belt_radii(belt);
+    let count = PI * (outer * outer - inner * inner) * ROCKS_PER_SPACING_SQUARED
+        / belt_spacing(belt).powi(2);
+    // radius is min + (max - min) * s² with s uniform in 0..1
+    let (min, range) = (ROCK_MIN_RADIUS, ROCK_MAX_RADIUS - ROCK_MIN_RADIUS);
+    let mean_square = min * min + 2.0 * min * range / 3.0 + range * range / 5.0;
+    count * mean_square
+}
+
+/// Rocks of one belt that fall inside a chunk.
+fn belt_rocks(index: usize, belt: &AsteroidBelt, coord: IVec2, seed: u64) -> Vec<Rock> {
+    let (inner, outer) = belt_radii(belt);
+    let min = coord.as_vec2() * CHUNK_SIZE;
+    let nearest = Vec2::ZERO.clamp(min, min + Vec2::splat(CHUNK_SIZE));
+    let farthest = Vec2::new(
+        if min.x.abs() > (min.x + CHUNK_SIZE).abs() {
+            min.x
+        } else {
+            min.x + CHUNK_SIZE
+        },
+        if min.y.abs() > (min.y + CHUNK_SIZE).abs() {
+            min.y
+        } else {
+            min.y + CHUNK_SIZE
+        },
+    );
+    if nearest.length() >= outer || farthest.length() < inner {
+        return Vec::new();
+    }
+
+    let mut rng = StdRng::seed_from_u64(chunk_seed(seed, coord));
+    let rock_area = expected_rock_area(belt);
+    // sampled a tile at a time, rocks in neighbouring chunks keep their
+    // distance too
+    PoissonSampler::new(Rect::default(), belt_spacing(belt))
+        .tiles(CHUNK_SIZE)
+        .tile(coord, seed)
+        .into_iter()
+        .filter(|p| (inner..outer).contains(&p.length()))
+        .map(|position| {
+            // mostly small rocks, the odd big one
+            let size: f32 = rng.gen_range(0.0..1.0);
+            let radius = ROCK_MIN_RADIUS + (ROCK_MAX_RADIUS - ROCK_MIN_RADIUS) * size * size;
+            let share = radius * radius / rock_area;
+            Rock {
+                position,
+                outline: outline(&mut rng, radius),
+                asteroid: Asteroid {
+                    belt: index,
+                    radius,
+                    yields: belt
+                        .yields
+                        .iter()
+                        .map(|deposit| ResourceYield {
+                            resource: deposit.resource,
+                            amount: (deposit.amount as f32 * share).round() as u32,
+                        })
+                        .collect(),
+                },
+                drift: Drift {
+                    spin: rng.gen_range(-0.5..0.5),
+                },
+            }
+        })
+        .collect()
+}
+
+/// The rocks in one chunk of the belts' frame, the same each time for the
+/// same seed.
+pub fn generate(asteroids: &SystemAsteroids, coord: IVec2) -> Vec<Rock> {
+    let seed = asteroids.seed ^ ASTEROID_SEED_SALT;
+    asteroids
+        .belts
+        .iter()
+        .enumerate()
+        .flat_map(|(i, belt)| belt_rocks(i, belt, coord, seed.wrapping_add(i as u64)))
+        .collect()
+}
+
+/// A chunk's rocks with their indices, less whatever has been mined out of
+/// them. Rocks that were mined dry stay gone.
+fn remaining(rocks: Vec<Rock>, delta: Option<&ChunkDelta>) -> Vec<(usize, Rock)> {
+    rocks
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, mut rock)| {
+            let Some(mined) = delta.and_then(|delta| delta.mined.get(&index)) else {
+                return Some((index, rock));
+            };
+            for deposit in mined {
+                rock.asteroid.mine(deposit.resource, deposit.amount);
+            }
+            (!rock.asteroid.is_depleted()).then_some((index, rock))
+        })
+        .collect()
+}
+
+/// Spawns a chunk's rocks under it. The colour comes from the vertices,
+/// the shared material lights them.
+pub(crate) fn spawn_rocks(
+    parent: &mut ChildBuilder,
+    meshes: &mut Assets<Mesh>,
+    material: &Handle<ColorMaterial>,
+    coord: IVec2,
+    rocks: Vec<Rock>,
+    delta: Option<&ChunkDelta>,
+) {
+    for (index, rock) in remaining(rocks, delta) {
+        parent.spawn((
+            ChunkItem { coord, index },
+            Mesh2d(meshes.add(rock.mesh())),
+            MeshMaterial2d(material.clone()),
+            Transform::from_translation(rock.position.extend(0.5)),
+            rock.collider(),
+            rock.drift,
+            rock.asteroid,
+        ));
+    }
+}
+
+/// Turns the belts, and with them the chunks their rocks are in, and
+/// tumbles each rock.
+pub(crate) fn drift_asteroids(
+    time: Res<Time>,
+    mut angle: ResMut<BeltAngle>,
+    mut chunks: Query<&mut Transform, (With<OrbitingChunk>, Without<Drift>)>,
+    mut rocks: Query<(&mut Transform, &Drift)>,
+) {
+    let delta = time.delta_secs();
+    angle.0 = (angle.0 + ORBIT_SPEED * delta).rem_euclid(TAU);
+    for mut transform in chunks.iter_mut() {
+        transform.rotation = angle.rotation();
+    }
+    for (mut transform, drift) in rocks.iter_mut() {
+        transform.rotate(Quat::from_rotation_z(drift.spin * delta));
+    }
+}
+
+/// Mines rocks into the cargo hold, crumbling the ones that run dry. What
+/// was mined is kept for when the rock's chunk loads again.
+pub(crate) fn mine_asteroids(
+    mut commands: Commands,
+    mut requests: EventReader<MineAsteroid>,
+    mut rocks: Query<(&mut Asteroid, &ChunkItem)>,
+    asteroids: Res<SystemAsteroids>,
+    mut deltas: ResMut<ChunkDeltas>,
+    mut cargo: ResMut<Cargo>,
+    mut mined_events: EventWriter<AsteroidMined>,
+) {
+    for request in requests.read() {
+        let Ok((mut asteroid, item)) = rocks.get_mut(request.asteroid) else {
+            continue;
+        };
+        let mined = asteroid.mine(request.resource, request.amount);
+        if mined > 0 {
+            deltas
+                .entry(asteroids.seed, item.coord)
+                .mine(item.index, request.resource, mined);
+            cargo.add_resource(request.resource, mined);
+            mined_events.send(AsteroidMined {
+                belt: asteroid.belt,
+                resource: request.resource,
+                amount: mined,
+            });
+        }
+        if asteroid.is_depleted() {
+            commands.entity(request.asteroid).despawn_recursive();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn asteroids() -> SystemAsteroids {
+        SystemAsteroids {
+            seed: 42,
+            belts: vec![AsteroidBelt {
+                orbit: 300.0,
+                density: 0.5,
+                yields: vec![
+                    ResourceYield {
+                        resource: ResourceKind::Iron,
+                        amount: 1000,
+                    },
+                    ResourceYield {
+                        resource: ResourceKind::Ice,
+                        amount: 400,
+                    },
+                ],
+            }],
+        }
+    }
+
+    /// Every rock of the belt, chunk by chunk.
+    fn all_rocks(asteroids: &SystemAsteroids) -> Vec<Rock> {
+        let reach = (belt_radii(&asteroids.belts[0]).1 / CHUNK_SIZE).ceil() as i32;
+        (-reach..reach)
+            .flat_map(|x| (-reach..reach).map(move |y| IVec2::new(x, y)))
+            .flat_map(|coord| generate(asteroids, coord))
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_same_rocks() {
+        let rocks = all_rocks(&asteroids());
+        assert!(!rocks.is_empty());
+        assert_eq!(rocks, all_rocks(&asteroids()));
+    }
+
+    #[test]
+    fn rocks_stay_in_their_chunk_and_belt() {
+        let asteroids = asteroids();
+        let orbit = asteroids.belts[0].orbit * ORBIT_SCALE;
+        let coord = IVec2::new(1, 0);
+        let rocks = generate(&asteroids, coord);
+        assert!(!rocks.is_empty());
+        for rock in &rocks {
+            assert_eq!(
+                crate::chunk::chunk_coord(rock.position),
+                coord,
+                "{:?}",
+                rock.position
+            );
+            assert!((rock.position.length() - orbit).abs() <= BELT_WIDTH / 2.0);
+        }
+        assert!(generate(&asteroids, IVec2::new(40, 40)).is_empty());
+    }
+
+    #[test]
+    fn rocks_share_out_the_belt_deposits() {
+        let asteroids = asteroids();
+        let rocks = all_rocks(&asteroids);
+        for deposit in &asteroids.belts[0].yields {
+            let total: u32 = rocks
+                .iter()
+                .flat_map(|r| &r.asteroid.yields)
+                .filter(|y| y.resource == deposit.resource)
+                .map(|y| y.amount)
+                .sum();
+            // deposits are spread by an estimate of the belt's rocks
+            assert!(
+                total > deposit.amount / 2,
+                "{} of {}",
+                total,
+                deposit.amount
+            );
+            assert!(
+                total < deposit.amount * 3 / 2,
+                "{} of {}",
+                total,
+                deposit.amount
+            );
+        }
+    }
+
+    #[test]
+    fn rocks_orbit_inside_their_belt() {
+        let asteroids = asteroids();
+        let orbit = asteroids.belts[0].orbit * ORBIT_SCALE;
+        let rock = &generate(&asteroids, IVec2::new(1, 0))[0];
+        // once around the star
+        for step in 0..360 {
+            let angle = BeltAngle(step as f32 / 360.0 * TAU);
+            let position = angle.to_world(rock.position);
+            assert!((position.length() - orbit).abs() <= BELT_WIDTH / 2.0);
+            assert!(angle.to_belt(position).distance(rock.position) < 1e-2);
+            let transform = Transform::from_rotation(angle.rotation())
+                .transform_point(rock.position.extend(0.0));
+            assert!(transform.truncate().distance(position) < 1e-2);
+        }
+    }
+
+    #[test]
+    fn mined_rocks_stay_mined() {
+        let asteroids = asteroids();
+        let coord = IVec2::new(1, 0);
+        let rocks = generate(&asteroids, coord);
+        assert!(rocks.len() >= 3);
+
+        let mut deltas = ChunkDeltas::default();
+        let delta = deltas.entry(asteroids.seed, coord);
+        delta.mine(0, ResourceKind::Iron, 1);
+        for deposit in &rocks[1].asteroid.yields {
+            delta.mine(1, deposit.resource, deposit.amount);
+        }
+
+        let reloaded = remaining(
+            generate(&asteroids, coord),
+            deltas.get(asteroids.seed, coord),
+        );
+        assert_eq!(reloaded.len(), rocks.len() - 1);
+        assert!(reloaded.iter().all(|(index, _)| *index != 1));
+        let iron = |rock: &Rock| rock.asteroid.yields[0].amount;
+        assert_eq!(iron(&reloaded[0].1), iron(&rocks[0]).saturating_sub(1));
+        // untouched elsewhere
+        assert_eq!(reloaded[1].1, rocks[2]);
+    }
+
+    #[test]
+    fn colliders_follow_the_rock() {
+        let rock = &all_rocks(&asteroids())[0];
+        let collider = rock.collider();
+        let transform = Transform::from_translation(rock.position.extend(0.0));
+        assert!(collider.contains(&transform, rock.position));
+        assert!(!collider.contains(
+            &transform,
+            rock.position + Vec2::splat(ROCK_MAX_RADIUS * 2.0)
+        ));
+    }
+
+    #[test]
+    fn mining_fills_the_hold_and_crumbles_rocks() {
+        use bevy::prelude::{App, Events, Update};
+
+        let mut app = App::new();
+        app.init_resource::<Cargo>()
+            .insert_resource(asteroids())
+            .init_resource::<ChunkDeltas>()
+            .add_event::<MineAsteroid>()
+            .add_event::<AsteroidMined>()
+            .add_systems(Update, mine_asteroids);
+        let rock = app
+            .world_mut()
+            .spawn((
+                Asteroid {
+                    belt: 0,
+                    radius: 10.0,
+                    yields: vec![ResourceYield {
+                        resource: ResourceKind::Iron,
+                        amount: 15,
+                    }],
+                },
+                ChunkItem {
+                    coord: IVec2::ONE,
+                    index: 3,
+                },
+            ))
+            .id();
+        for _ in 0..2 {
+            app.world_mut().send_event(MineAsteroid {
+                asteroid: rock,
+                resource: ResourceKind::Iron,
+                amount: 10,
+            });
+        }
+        app.update();
+
+        assert_eq!(
+            app.world().resource::<Cargo>().resource(ResourceKind::Iron),
+            15
+        );
+        assert_eq!(app.world().resource::<Events<AsteroidMined>>().len(), 2);
+        assert!(app.world().get_entity(rock).is_err());
+        // and it stays mined when its chunk loads again
+        let deltas = app.world().resource::<ChunkDeltas>();
+        assert_eq!(
+            deltas.get(42, IVec2::ONE).unwrap().mined[&3],
+            vec![ResourceYield {
+                resource: ResourceKind::Iron,
+                amount: 15
+            }]
+        );
+    }
+}
@@ -0,0 +1,246 @@
+use std::f64::consts::TAU;
+
+use bevy::{
+    image::{ImageAddressMode, ImageSampler, ImageSamplerDescriptor},
+    prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+    },
+};
+use noise::{Fbm, MultiFractal, NoiseFn, Perlin};
+
+use crate::stars::{StarfieldMaterial, StarfieldSeed};
+
+/// Side length of the nebula texture in texels. It tiles, so it can be small.
+const NEBULA_TEXTURE_SIZE: u32 = 256;
+
+/// World units covered by one nebula texel.
+const NEBULA_TEXEL_SCALE: f32 = 12.0;
+
+/// Size of the nebula sprite, big enough to cover the view when zoomed out.
+const NEBULA_SPRITE_SIZE: f32 = 16384.0;
+
+/// Share of the camera's movement the nebula follows, less than the
+/// farthest star layer so it reads as further away still.
+const NEBULA_PARALLAX: f32 = 0.02;
+
+/// Nebula density everywhere, before hazards and anomalies add to it.
+const BASE_NEBULA_DENSITY: f32 = 0.15;
+
+/// Mixed into the starfield seed so the nebula doesn't line up with stars.
+const NEBULA_SEED_SALT: u32 = 0x6e65_6273;
+
+/// What the current system looks like from the inside. The game fills this
+/// in from the system the player is in.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct SystemBackdrop {
+    /// 0.0 is icy, 1.0 is scorching.
+    pub temperature: f32,
+    /// Nebula hazard intensity, 0.0 to 1.0.
+    pub nebula: f32,
+    /// Anomalies colour the nebula strangely.
+    pub anomaly: bool,
+}
+
+impl Default for SystemBackdrop {
+    fn default() -> Self {
+        Self {
+            temperature: 0.5,
+            nebula: 0.0,
+            anomaly: false,
+        }
+    }
+}
+
+fn lerp_color(a: Color, b: Color, t: f32) -> Color {
+    a.mix(&b, t.clamp(0.0, 1.0))
+}
+
+impl SystemBackdrop {
+    /// Tint over the whole starfield, icy blue through white to orange.
+    pub fn star_tint(&self) -> Color {
+        let icy = Color::srgb(0.65, 0.8, 1.0);
+        let neutral = Color::WHITE;
+        let hot = Color::srgb(1.0, 0.72, 0.45);
+        if self.temperature < 0.5 {
+            lerp_color(icy, neutral, self.temperature * 2.0)
+        } else {
+            lerp_color(neutral, hot, (self.temperature - 0.5) * 2.0)
+        }
+    }
+
+    /// Colour of the nebula's densest parts.
+    pub fn nebula_color(&self) -> Color {
+        let icy = Color::srgb(0.2, 0.55, 0.8);
+        let hot = Color::srgb(0.85, 0.3, 0.15);
+        let color = lerp_color(icy, hot, self.temperature);
+        if self.anomaly {
+            lerp_color(color, Color::srgb(0.6, 0.2, 0.85), 0.6)
+        } else {
+            color
+        }
+    }
+
+    pub fn nebula_density(&self) -> f32 {
+        let anomaly = if self.anomaly { 0.2 } else { 0.0 };
+        (BASE_NEBULA_DENSITY + self.nebula * 0.6 + anomaly).min(1.0)
+    }
+
+    /// Colour behind everything, a dark shade of the nebula.
+    pub fn background(&self) -> Color {
+        lerp_color(Color::BLACK, self.nebula_color(), 0.08)
+    }
+
+    /// Stands in for ambient light, which unlit sprites and 2D materials
+    /// ignore: multiplied into the nebula and scenery so they're lit like
+    /// the stars.
+    pub fn ambient(&self) -> Color {
+        lerp_color(self.star_tint(), self.nebula_color(), 0.3)
+    }
+}
+
+/// A tileable nebula texture: noise sampled on a torus so opposite edges
+/// match up.
+pub fn nebula_image(backdrop: &SystemBackdrop, seed: u64) -> Image {
+    let noise = Fbm::<Perlin>::new(seed as u32 ^ NEBULA_SEED_SALT)
+        .set_octaves(5)
+        .set_frequency(1.5);
+    let color = backdrop.nebula_color().to_srgba();
+    let density = backdrop.nebula_density();
+
+    let size = NEBULA_TEXTURE_SIZE as usize;
+    let mut data = Vec::with_capacity(size * size * 4);
+    for y in 0..size {
+        for x in 0..size {
+            let u = x as f64 / size as f64 * TAU;
+            let v = y as f64 / size as f64 * TAU;
+            let value = noise.get([u.cos(), u.sin(), v.cos(), v.sin()]);
+            // only the crests of the noise show, with soft edges
+            let cloud = ((value as f32 + 0.1) * 1.5).clamp(0.0, 1.0);
+            let alpha = cloud * cloud * density;
+            data.extend([
+                (color.red * 255.0) as u8,
+                (color.green * 255.0) as u8,
+                (color.blue * 255.0) as u8,
+                (alpha * 255.0) as u8,
+            ]);
+        }
+    }
+
+    let mut image = Image::new(
+        Extent3d {
+            width: NEBULA_TEXTURE_SIZE,
+            height: NEBULA_TEXTURE_SIZE,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+    image.sampler = ImageSampler::Descriptor(ImageSamplerDescriptor {
+        address_mode_u: ImageAddressMode::Repeat,
+        address_mode_v: ImageAddressMode::Repeat,
+        ..ImageSamplerDescriptor::linear()
+    });
+    image
+}
+
+#[derive(Component)]
+pub struct Nebula;
+
+pub(crate) fn spawn_nebula(mut commands: Commands) {
+    commands.spawn((
+        Nebula,
+        Sprite {
+            custom_size: Some(Vec2::splat(NEBULA_SPRITE_SIZE)),
+            ..default()
+        },
+        // behind every star layer
+        Transform::from_xyz(0.0, 0.0, -1.0),
+    ));
+}
+
+/// Recolours the scene whenever the player arrives somewhere new.
+pub(crate) fn apply_backdrop(
+    backdrop: Res<SystemBackdrop>,
+    seed: Res<StarfieldSeed>,
+    star_material: Option<Res<StarfieldMaterial>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+    mut nebulae: Query<&mut Sprite, With<Nebula>>,
+) {
+    if let Some(material) = star_material.and_then(|handle| materials.get_mut(&handle.0)) {
+        material.color = backdrop.star_tint();
+    }
+
+    let image = images.add(nebula_image(&backdrop, seed.0));
+    for mut sprite in nebulae.iter_mut() {
+        sprite.image = image.clone();
+        sprite.color = backdrop.ambient();
+    }
+}
+
+pub(crate) fn apply_background(backdrop: Res<SystemBackdrop>, mut clear_color: ResMut<ClearColor>) {
+    clear_color.0 = backdrop.background();
+}
+
+/// Keeps the nebula centred on the camera, scrolling its texture slowly so
+/// it drifts like a very distant layer.
+pub(crate) fn parallax_nebula(
+    cameras: Query<&Transform, (With<Camera2d>, Without<Nebula>)>,
+    mut nebulae: Query<(&mut Transform, &mut Sprite), With<Nebula>>,
+) {
+    let Ok(camera) = cameras.get_single() else {
+        return;
+    };
+    let camera = camera.translation.truncate();
+    let texels = NEBULA_SPRITE_SIZE / NEBULA_TEXEL_SCALE;
+    // the y axis of textures points down
+    let scroll = Vec2::new(camera.x, -camera.y) * NEBULA_PARALLAX / NEBULA_TEXEL_SCALE;
+    let min = scroll.rem_euclid(Vec2::splat(NEBULA_TEXTURE_SIZE as f32));
+
+    for (mut transform, mut sprite) in nebulae.iter_mut() {
+        transform.translation.x = camera.x;
+        transform.translation.y = camera.y;
+        sprite.rect = Some(Rect::from_corners(min, min + Vec2::splat(texels)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hot_systems_look_warmer() {
+        let icy = SystemBackdrop {
+            temperature: 0.0,
+            ..default()
+        };
+        let hot = SystemBackdrop {
+            temperature: 1.0,
+            ..default()
+        };
+        for tint in [SystemBackdrop::star_tint, SystemBackdrop::ambient] {
+            let (icy, hot) = (tint(&icy).to_srgba(), tint(&hot).to_srgba());
+            assert!(hot.red > hot.blue);
+            assert!(icy.blue > icy.red);
+        }
+    }
+
+    #[test]
+    fn nebulae_thicken_the_backdrop() {
+        let clear = SystemBackdrop::default();
+        let nebula = SystemBackdrop {
+            nebula: 1.0,
+            ..default()
+        };
+        assert!(nebula.nebula_density() > clear.nebula_density());
+        let alpha = |backdrop: &SystemBackdrop| -> u64 {
+            let image = nebula_image(backdrop, 1);
+            image.data.chunks(4).map(|texel| texel[3] as u64).sum()
+        };
+        assert!(alpha(&nebula) > alpha(&clear));
+    }
+}
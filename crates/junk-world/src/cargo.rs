@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+
+use bevy::prelude::Resource;
+use junk_unav::{LootKind, ResourceKind};
+
+use crate::encounter::{Effect, Resolution};
+
+/// What the player has picked up: quest rewards, salvage and anything
+/// brought back from the surface.
+#[derive(Resource, Clone, Debug, Default, PartialEq)]
+pub struct Cargo {
+    pub credits: u32,
+    pub resources: HashMap<ResourceKind, u32>,
+    /// Spare parts, salvaged or paid out.
+    pub parts: u32,
+}
+
+impl Cargo {
+    pub fn resource(&self, resource: ResourceKind) -> u32 {
+        self.resources.get(&resource).copied().unwrap_or(0)
+    }
+
+    pub fn add_resource(&mut self, resource: ResourceKind, amount: u32) {
+        *self.resources.entry(resource).or_default() += amount;
+    }
+
+    pub fn add_loot(&mut self, kind: LootKind, amount: u32) {
+        match kind {
+            LootKind::Credits => self.credits += amount,
+            LootKind::Resource(resource) => self.add_resource(resource, amount),
+            LootKind::Part => self.parts += amount,
+        }
+    }
+
+    /// Stows whatever a landing party brought back.
+    pub fn apply(&mut self, resolution: &Resolution) {
+        for effect in &resolution.effects {
+            match effect {
+                Effect::Resource(resource, amount) => self.add_resource(*resource, *amount),
+                Effect::Parts(amount) => self.parts += amount,
+                Effect::Damage(_) | Effect::CrewLost(_) => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loot_goes_where_it_belongs() {
+        let mut cargo = Cargo::default();
+        cargo.add_loot(LootKind::Credits, 120);
+        cargo.add_loot(LootKind::Resource(ResourceKind::Iron), 3);
+        cargo.add_loot(LootKind::Resource(ResourceKind::Iron), 2);
+        cargo.add_loot(LootKind::Part, 1);
+        assert_eq!(cargo.credits, 120);
+        assert_eq!(cargo.resource(ResourceKind::Iron), 5);
+        assert_eq!(cargo.resource(ResourceKind::Ice), 0);
+        assert_eq!(cargo.parts, 1);
+    }
+
+    #[test]
+    fn landings_fill_the_hold() {
+        let mut cargo = Cargo::default();
+        cargo.apply(&Resolution {
+            encounter: "Ore vein".to_string(),
+            roll: 7,
+            bonus: 0,
+            success: true,
+            effects: vec![
+                Effect::Resource(ResourceKind::Nickel, 4),
+                Effect::Parts(2),
+                Effect::Damage(10),
+            ],
+        });
+        assert_eq!(cargo.resource(ResourceKind::Nickel), 4);
+        assert_eq!(cargo.parts, 2);
+    }
+}
@@ -0,0 +1,224 @@
+use std::collections::HashMap;
+
+use bevy::{
+    asset::{Assets, Handle},
+    math::{IVec2, Vec2},
+    prelude::{
+        BuildChildren, Camera2d, Commands, Component, DespawnRecursiveExt, DetectChanges, Entity,
+        Has, InheritedVisibility, Mesh, Or, Query, Res, ResMut, Resource, Transform, With,
+    },
+    sprite::ColorMaterial,
+};
+use junk_ship::{PartsResource, PlayerShip};
+use junk_unav::ChunkDeltas;
+
+use crate::{
+    asteroid::{self, spawn_rocks, BeltAngle, SystemAsteroids},
+    backdrop::SystemBackdrop,
+    station::{container_mesh, spawn_station, station_chunk, StationLayout, SystemStation},
+};
+
+/// Side length of a chunk in world units.
+pub const CHUNK_SIZE: f32 = 512.0;
+
+/// Chunks this many steps from the player's chunk are loaded.
+const LOAD_RADIUS: i32 = 2;
+
+/// Loaded chunks this many steps away are dropped. A step more than
+/// `LOAD_RADIUS` so flying along a chunk edge doesn't reload it every frame.
+const UNLOAD_RADIUS: i32 = 3;
+
+/// The chunk a world position falls in.
+pub fn chunk_coord(position: Vec2) -> IVec2 {
+    (position / CHUNK_SIZE).floor().as_ivec2()
+}
+
+/// Seed for one chunk, so chunks can be generated in any order and come out
+/// the same.
+pub fn chunk_seed(seed: u64, coord: IVec2) -> u64 {
+    junk_procgen::tile_seed(seed, coord)
+}
+
+/// Parent of everything spawned for one chunk. Despawning it unloads the
+/// chunk; its contents are regenerated when it loads again, with what the
+/// player changed in it applied from `ChunkDeltas`.
+#[derive(Component)]
+pub struct Chunk {
+    pub coord: IVec2,
+}
+
+/// Marks a chunk of the asteroid belts. Its coordinate is in the belts'
+/// frame and it turns with them, so its rocks never drift out of it.
+#[derive(Component)]
+pub struct OrbitingChunk;
+
+/// Where something spawned with a chunk came from, so changes to it can be
+/// kept for when the chunk loads again.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChunkItem {
+    pub coord: IVec2,
+    /// Index among the things of its kind generated for the chunk.
+    pub index: usize,
+}
+
+#[derive(Resource, Default)]
+pub struct LoadedChunks {
+    /// Chunks that stay put, where the station is.
+    pub fixed: HashMap<IVec2, Entity>,
+    /// Chunks of the turning asteroid belts.
+    pub orbiting: HashMap<IVec2, Entity>,
+}
+
+/// Shared by every chunk, so loading one doesn't add materials.
+#[derive(Resource)]
+pub struct ChunkMaterials {
+    /// For meshes that carry their colour in their vertices. Its own colour
+    /// is the system's ambient light.
+    pub vertex: Handle<ColorMaterial>,
+    pub container_mesh: Handle<Mesh>,
+}
+
+pub(crate) fn init_chunk_materials(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    backdrop: Res<SystemBackdrop>,
+) {
+    commands.insert_resource(ChunkMaterials {
+        vertex: materials.add(ColorMaterial::from_color(backdrop.ambient())),
+        container_mesh: meshes.add(container_mesh()),
+    });
+}
+
+/// Relights everything in the chunks when the backdrop changes.
+pub(crate) fn light_chunks(
+    backdrop: Res<SystemBackdrop>,
+    chunk_materials: Res<ChunkMaterials>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    if let Some(material) = materials.get_mut(&chunk_materials.vertex) {
+        material.color = backdrop.ambient();
+    }
+}
+
+/// Stations are built from ship parts, which load in after startup. Once
+/// they're in, chunks are reloaded to pick the station up.
+pub(crate) fn parts_changed(parts: Option<Res<PartsResource>>) -> bool {
+    parts.is_some_and(|parts| parts.is_changed())
+}
+
+/// Drops every chunk when the player arrives somewhere new, the streaming
+/// loads the new system's around them.
+pub(crate) fn reset_chunks(mut commands: Commands, mut loaded: ResMut<LoadedChunks>) {
+    let LoadedChunks { fixed, orbiting } = &mut *loaded;
+    for (_, entity) in fixed.drain().chain(orbiting.drain()) {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn chunk_distance(a: IVec2, b: IVec2) -> i32 {
+    (a - b).abs().max_element()
+}
+
+/// What streaming centres on: the player's ship, or the camera before
+/// there is one.
+type Followed = Or<(With<PlayerShip>, With<Camera2d>)>;
+
+/// Drops the chunks left behind and returns the coordinates around
+/// `centre` that still need loading.
+fn stream(
+    commands: &mut Commands,
+    loaded: &mut HashMap<IVec2, Entity>,
+    centre: IVec2,
+) -> Vec<IVec2> {
+    loaded.retain(|coord, entity| {
+        let keep = chunk_distance(*coord, centre) <= UNLOAD_RADIUS;
+        if !keep {
+            commands.entity(*entity).despawn_recursive();
+        }
+        keep
+    });
+
+    (-LOAD_RADIUS..=LOAD_RADIUS)
+        .flat_map(|x| (-LOAD_RADIUS..=LOAD_RADIUS).map(move |y| centre + IVec2::new(x, y)))
+        .filter(|coord| !loaded.contains_key(coord))
+        .collect()
+}
+
+/// Loads the chunks around the player's ship, or the camera before there is
+/// one, and unloads those left behind. Station chunks are found from the
+/// world position, belt chunks from where it is in the turning belts.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn stream_chunks(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    materials: Res<ChunkMaterials>,
+    asteroids: Res<SystemAsteroids>,
+    station: Res<SystemStation>,
+    parts: Option<Res<PartsResource>>,
+    angle: Res<BeltAngle>,
+    deltas: Res<ChunkDeltas>,
+    mut loaded: ResMut<LoadedChunks>,
+    followed: Query<(&Transform, Has<PlayerShip>), Followed>,
+) {
+    let centre = followed
+        .iter()
+        .max_by_key(|(_, player)| *player)
+        .map(|(transform, _)| transform.translation.truncate());
+    let Some(centre) = centre else {
+        return;
+    };
+
+    for coord in stream(&mut commands, &mut loaded.fixed, chunk_coord(centre)) {
+        let layout = match &parts {
+            Some(parts) if coord == station_chunk() => {
+                StationLayout::generate(&station, parts.all_parts())
+            }
+            _ => None,
+        };
+        let entity = commands
+            .spawn((
+                Chunk { coord },
+                Transform::default(),
+                InheritedVisibility::default(),
+            ))
+            .with_children(|parent| {
+                if let Some(layout) = layout {
+                    let delta = deltas.get(station.seed, coord);
+                    spawn_station(parent, &mut meshes, &materials, layout, delta);
+                }
+            })
+            .id();
+        loaded.fixed.insert(coord, entity);
+    }
+
+    let belt_centre = chunk_coord(angle.to_belt(centre));
+    for coord in stream(&mut commands, &mut loaded.orbiting, belt_centre) {
+        let rocks = asteroid::generate(&asteroids, coord);
+        let delta = deltas.get(asteroids.seed, coord);
+        let entity = commands
+            .spawn((
+                Chunk { coord },
+                OrbitingChunk,
+                Transform::from_rotation(angle.rotation()),
+                InheritedVisibility::default(),
+            ))
+            .with_children(|parent| {
+                spawn_rocks(parent, &mut meshes, &materials.vertex, coord, rocks, delta);
+            })
+            .id();
+        loaded.orbiting.insert(coord, entity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positions_map_to_chunks() {
+        assert_eq!(chunk_coord(Vec2::new(10.0, 10.0)), IVec2::ZERO);
+        assert_eq!(chunk_coord(Vec2::new(-1.0, 600.0)), IVec2::new(-1, 1));
+        assert_eq!(chunk_distance(IVec2::new(-2, 1), IVec2::new(1, 0)), 3);
+    }
+}
@@ -0,0 +1,156 @@
+use bevy::{
+    asset::{io::Reader, AssetLoader, LoadContext},
+    prelude::*,
+};
+use serde::Deserialize;
+
+/// Keyframes `(t, value)` sorted by `t` in 0..1, where 0 is the farthest
+/// layer and 1 the nearest. Values in between are interpolated linearly.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct Curve<T>(pub Vec<(f32, T)>);
+
+impl<T: Copy> Curve<T> {
+    fn sample(&self, t: f32, lerp: impl Fn(T, T, f32) -> T) -> Option<T> {
+        let keys = &self.0;
+        let first = keys.first()?;
+        if t <= first.0 {
+            return Some(first.1);
+        }
+        for pair in keys.windows(2) {
+            let ((t0, a), (t1, b)) = (pair[0], pair[1]);
+            if t <= t1 {
+                let local = if t1 > t0 { (t - t0) / (t1 - t0) } else { 1.0 };
+                return Some(lerp(a, b, local));
+            }
+        }
+        keys.last().map(|key| key.1)
+    }
+}
+
+impl Curve<f32> {
+    pub fn at(&self, t: f32) -> f32 {
+        self.sample(t, |a, b, t| a + (b - a) * t).unwrap_or(0.0)
+    }
+}
+
+impl Curve<[f32; 4]> {
+    /// Colour at `t`, keyframes are sRGBA.
+    pub fn at(&self, t: f32) -> Color {
+        let [r, g, b, a] = self
+            .sample(t, |a, b, t| {
+                std::array::from_fn(|i| a[i] + (b[i] - a[i]) * t)
+            })
+            .unwrap_or([1.0; 4]);
+        Color::srgba(r, g, b, a)
+    }
+}
+
+/// How the starfield is laid out, tuned by art through
+/// `assets/starfield/default.starfield.ron`.
+#[derive(Asset, Resource, TypePath, Clone, Debug, PartialEq, Deserialize)]
+pub struct StarfieldConfig {
+    /// Number of layers beyond the farthest one.
+    pub layers: usize,
+    /// Side length of the square each layer repeats over.
+    pub extent: usize,
+    /// Poisson sampling attempts per point, higher packs stars tighter.
+    pub attempts: usize,
+    /// Minimum distance between stars in a layer, lower is denser.
+    pub min_dist: Curve<f32>,
+    /// Star size in world units.
+    pub size: Curve<f32>,
+    /// Each star's size varies randomly by up to this fraction.
+    pub size_jitter: f32,
+    pub color: Curve<[f32; 4]>,
+}
+
+impl Default for StarfieldConfig {
+    // the layout the starfield had before it was configurable, keep in step
+    // with the default asset so nothing pops when it loads
+    fn default() -> Self {
+        Self {
+            layers: 15,
+            extent: 4096,
+            attempts: 90,
+            min_dist: Curve(vec![(0.0, 200.0), (1.0, 200.0)]),
+            size: Curve(vec![(0.0, 0.0), (1.0, 4.0)]),
+            size_jitter: 0.0,
+            color: Curve(vec![
+                (0.0, [1.0, 0.95, 1.0, 0.0]),
+                (1.0, [1.0, 0.95, 0.75, 1.0]),
+            ]),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct StarfieldConfigLoader;
+
+impl AssetLoader for StarfieldConfigLoader {
+    type Asset = StarfieldConfig;
+    type Settings = ();
+    type Error = anyhow::Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(ron::de::from_bytes(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["starfield.ron"]
+    }
+}
+
+#[derive(Resource)]
+pub struct StarfieldConfigHandle(pub Handle<StarfieldConfig>);
+
+pub(crate) fn load_starfield_config(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let handle = asset_server.load("starfield/default.starfield.ron");
+    commands.insert_resource(StarfieldConfigHandle(handle));
+}
+
+/// Copies the config asset into the resource whenever it (re)loads.
+pub(crate) fn apply_starfield_config(
+    handle: Option<Res<StarfieldConfigHandle>>,
+    assets: Res<Assets<StarfieldConfig>>,
+    mut events: EventReader<AssetEvent<StarfieldConfig>>,
+    mut config: ResMut<StarfieldConfig>,
+) {
+    let Some(handle) = handle else {
+        return;
+    };
+    for event in events.read() {
+        if event.is_loaded_with_dependencies(&handle.0) || event.is_modified(&handle.0) {
+            if let Some(loaded) = assets.get(&handle.0) {
+                config.set_if_neq(loaded.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn curves_interpolate() {
+        let curve = Curve(vec![(0.0, 10.0), (0.5, 20.0), (1.0, 0.0)]);
+        assert_eq!(curve.at(-1.0), 10.0);
+        assert_eq!(curve.at(0.25), 15.0);
+        assert_eq!(curve.at(0.75), 10.0);
+        assert_eq!(curve.at(2.0), 0.0);
+    }
+
+    #[test]
+    fn default_asset_matches_default() {
+        let asset = include_str!("../../junk/assets/starfield/default.starfield.ron");
+        let config: StarfieldConfig = ron::from_str(asset).unwrap();
+        assert_eq!(config, StarfieldConfig::default());
+    }
+}
@@ -0,0 +1,482 @@
+use std::collections::HashMap;
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, LoadContext},
+    prelude::*,
+};
+use junk_ship::{DamageShipEvent, PartsResource, PlayerShip, ShipComponent, ShipMetrics};
+use junk_unav::{Biome, ResourceKind};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use serde::Deserialize;
+
+use crate::cargo::Cargo;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+pub enum Skill {
+    Piloting,
+    Engineering,
+    Science,
+    Combat,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct CrewMember {
+    pub name: String,
+    pub skills: HashMap<Skill, u32>,
+}
+
+impl CrewMember {
+    pub fn skill(&self, skill: Skill) -> u32 {
+        self.skills.get(&skill).copied().unwrap_or(0)
+    }
+}
+
+/// Everyone aboard the player's ship.
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct Crew(pub Vec<CrewMember>);
+
+impl Default for Crew {
+    fn default() -> Self {
+        let member = |name: &str, skill: Skill| CrewMember {
+            name: name.to_string(),
+            skills: HashMap::from([(skill, 2)]),
+        };
+        Self(vec![
+            member("Vasquez", Skill::Piloting),
+            member("Okafor", Skill::Engineering),
+            member("Lindqvist", Skill::Science),
+        ])
+    }
+}
+
+impl Crew {
+    /// The best anyone aboard is at a skill, they're the one who goes.
+    pub fn best(&self, skill: Skill) -> u32 {
+        self.0.iter().map(|m| m.skill(skill)).max().unwrap_or(0)
+    }
+
+    /// Removes whoever didn't make it back.
+    pub fn apply(&mut self, resolution: &Resolution) {
+        for effect in &resolution.effects {
+            if let Effect::CrewLost(name) = effect {
+                self.0.retain(|m| &m.name != name);
+            }
+        }
+    }
+}
+
+/// A ship metric that helps with a check.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum ShipStat {
+    Armor,
+    Cargo,
+    Thrust,
+    Acceleration,
+    Crew,
+}
+
+impl ShipStat {
+    /// What the stat adds to a roll. Scaled so a typical ship gets a few
+    /// points, roughly what a skilled crew member adds.
+    pub fn bonus(&self, metrics: &ShipMetrics) -> u32 {
+        match self {
+            ShipStat::Armor => metrics.armor as u32 / 10,
+            ShipStat::Cargo => metrics.cargo_capacity as u32 / 100,
+            ShipStat::Thrust => metrics.thrust as u32 / 75,
+            ShipStat::Acceleration => (metrics.acceleration() * 4.0).round() as u32,
+            ShipStat::Crew => metrics.crew_capacity as u32,
+        }
+    }
+}
+
+/// Roll `dice` six-sided dice, add the crew's best `skill` and the ship's
+/// `stat`, and meet `difficulty` to succeed.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct Check {
+    pub dice: u32,
+    #[serde(default)]
+    pub skill: Option<Skill>,
+    #[serde(default)]
+    pub stat: Option<ShipStat>,
+    pub difficulty: u32,
+}
+
+/// What can happen, with amounts rolled when it does.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub enum Outcome {
+    Resource {
+        resource: ResourceKind,
+        min: u32,
+        max: u32,
+    },
+    Parts {
+        min: u32,
+        max: u32,
+    },
+    Damage {
+        min: u32,
+        max: u32,
+    },
+    CrewLoss {
+        count: u32,
+    },
+}
+
+impl Outcome {
+    /// The range amounts are rolled from, for outcomes that roll one.
+    fn range(&self) -> Option<(u32, u32)> {
+        match self {
+            Outcome::Resource { min, max, .. }
+            | Outcome::Parts { min, max }
+            | Outcome::Damage { min, max } => Some((*min, *max)),
+            Outcome::CrewLoss { .. } => None,
+        }
+    }
+}
+
+/// What did happen.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Effect {
+    Resource(ResourceKind, u32),
+    Parts(u32),
+    Damage(u32),
+    CrewLost(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct Encounter {
+    pub name: String,
+    pub text: String,
+    /// Relative chance of this encounter being drawn.
+    pub weight: u32,
+    pub check: Check,
+    #[serde(default)]
+    pub success: Vec<Outcome>,
+    #[serde(default)]
+    pub failure: Vec<Outcome>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Resolution {
+    pub encounter: String,
+    /// Sum of the dice alone.
+    pub roll: u32,
+    /// Skill and ship bonuses on top.
+    pub bonus: u32,
+    pub success: bool,
+    pub effects: Vec<Effect>,
+}
+
+impl Resolution {
+    /// Total hull damage taken.
+    pub fn damage(&self) -> u32 {
+        self.effects
+            .iter()
+            .map(|effect| match effect {
+                Effect::Damage(amount) => *amount,
+                _ => 0,
+            })
+            .sum()
+    }
+}
+
+impl Encounter {
+    pub fn resolve(&self, rng: &mut impl Rng, metrics: &ShipMetrics, crew: &Crew) -> Resolution {
+        let roll: u32 = (0..self.check.dice).map(|_| rng.gen_range(1..=6)).sum();
+        let bonus = self.check.skill.map_or(0, |skill| crew.best(skill))
+            + self.check.stat.map_or(0, |stat| stat.bonus(metrics));
+        let success = roll + bonus >= self.check.difficulty;
+
+        let outcomes = if success {
+            &self.success
+        } else {
+            &self.failure
+        };
+        let mut survivors: Vec<&CrewMember> = crew.0.iter().collect();
+        let mut effects = Vec::new();
+        for outcome in outcomes {
+            match outcome {
+                Outcome::Resource { resource, min, max } => {
+                    effects.push(Effect::Resource(*resource, rng.gen_range(*min..=*max)))
+                }
+                Outcome::Parts { min, max } => {
+                    effects.push(Effect::Parts(rng.gen_range(*min..=*max)))
+                }
+                Outcome::Damage { min, max } => {
+                    effects.push(Effect::Damage(rng.gen_range(*min..=*max)))
+                }
+                Outcome::CrewLoss { count } => {
+                    for _ in 0..*count {
+                        if survivors.is_empty() {
+                            break;
+                        }
+                        let lost = survivors.swap_remove(rng.gen_range(0..survivors.len()));
+                        effects.push(Effect::CrewLost(lost.name.clone()));
+                    }
+                }
+            }
+        }
+
+        Resolution {
+            encounter: self.name.clone(),
+            roll,
+            bonus,
+            success,
+            effects,
+        }
+    }
+}
+
+/// What may happen on a planet's surface, by biome. Written in
+/// `assets/encounters/planets.encounters.ron`.
+#[derive(Asset, Resource, TypePath, Clone, Debug, Default, PartialEq, Deserialize)]
+pub struct EncounterTables(pub HashMap<Biome, Vec<Encounter>>);
+
+impl EncounterTables {
+    /// Checks every range can be rolled, so a typo in the tables is a load
+    /// error rather than a panic on landing.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        for (biome, encounters) in &self.0 {
+            for encounter in encounters {
+                for outcome in encounter.success.iter().chain(&encounter.failure) {
+                    if let Some((min, max)) = outcome.range() {
+                        if min > max {
+                            anyhow::bail!(
+                                "{:?} encounter {:?} rolls {}..={}, min is above max",
+                                biome,
+                                encounter.name,
+                                min,
+                                max
+                            );
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn draw(&self, biome: Biome, rng: &mut impl Rng) -> Option<&Encounter> {
+        self.0
+            .get(&biome)?
+            .choose_weighted(rng, |encounter| encounter.weight)
+            .ok()
+    }
+}
+
+#[derive(Default)]
+pub struct EncounterTablesLoader;
+
+impl AssetLoader for EncounterTablesLoader {
+    type Asset = EncounterTables;
+    type Settings = ();
+    type Error = anyhow::Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let tables: EncounterTables = ron::de::from_bytes(&bytes)?;
+        tables.validate()?;
+        Ok(tables)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["encounters.ron"]
+    }
+}
+
+#[derive(Resource)]
+pub struct EncounterTablesHandle(pub Handle<EncounterTables>);
+
+pub(crate) fn load_encounter_tables(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let handle = asset_server.load("encounters/planets.encounters.ron");
+    commands.insert_resource(EncounterTablesHandle(handle));
+}
+
+/// Copies the tables asset into the resource whenever it (re)loads.
+pub(crate) fn apply_encounter_tables(
+    handle: Option<Res<EncounterTablesHandle>>,
+    assets: Res<Assets<EncounterTables>>,
+    mut events: EventReader<AssetEvent<EncounterTables>>,
+    mut tables: ResMut<EncounterTables>,
+) {
+    let Some(handle) = handle else {
+        return;
+    };
+    for event in events.read() {
+        if event.is_loaded_with_dependencies(&handle.0) || event.is_modified(&handle.0) {
+            if let Some(loaded) = assets.get(&handle.0) {
+                tables.set_if_neq(loaded.clone());
+            }
+        }
+    }
+}
+
+/// Send to land on a planet. The same seed always plays out the same way.
+#[derive(Event)]
+pub struct ExplorePlanet {
+    pub planet: String,
+    pub biome: Biome,
+    pub seed: u64,
+}
+
+#[derive(Event)]
+pub struct PlanetExplored {
+    pub planet: String,
+    pub resolution: Resolution,
+}
+
+pub(crate) fn explore_planets(
+    mut explore_events: EventReader<ExplorePlanet>,
+    mut explored_events: EventWriter<PlanetExplored>,
+    tables: Res<EncounterTables>,
+    parts: Res<PartsResource>,
+    player: Query<&ShipComponent, With<PlayerShip>>,
+    mut crew: ResMut<Crew>,
+) {
+    for event in explore_events.read() {
+        let Ok(ship) = player.get_single() else {
+            warn!("No ship to explore {} with", event.planet);
+            continue;
+        };
+        let mut rng = StdRng::seed_from_u64(event.seed);
+        let Some(encounter) = tables.draw(event.biome, &mut rng) else {
+            continue;
+        };
+        let metrics = ship.ship.metrics(parts.all_parts());
+        let resolution = encounter.resolve(&mut rng, &metrics, &crew);
+        crew.apply(&resolution);
+        explored_events.send(PlanetExplored {
+            planet: event.planet.clone(),
+            resolution,
+        });
+    }
+}
+
+/// Stows what the landing party found and hands any damage to the ship.
+pub(crate) fn apply_explorations(
+    mut explored_events: EventReader<PlanetExplored>,
+    player: Query<Entity, With<PlayerShip>>,
+    mut cargo: ResMut<Cargo>,
+    mut damage_events: EventWriter<DamageShipEvent>,
+) {
+    for PlanetExplored { resolution, .. } in explored_events.read() {
+        cargo.apply(resolution);
+        let damage = resolution.damage();
+        if damage == 0 {
+            continue;
+        }
+        if let Ok(ship) = player.get_single() {
+            damage_events.send(DamageShipEvent {
+                ship,
+                amount: damage,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tables() -> EncounterTables {
+        let asset = include_str!("../../junk/assets/encounters/planets.encounters.ron");
+        ron::from_str(asset).unwrap()
+    }
+
+    fn metrics(armor: usize) -> ShipMetrics {
+        ShipMetrics {
+            crew_capacity: 1,
+            armor,
+            cargo_capacity: 200,
+            thrust: 75,
+            weight: 40,
+        }
+    }
+
+    #[test]
+    fn every_biome_has_encounters() {
+        let tables = tables();
+        let mut rng = StdRng::seed_from_u64(1);
+        for biome in [Biome::Ice, Biome::Rock, Biome::Gas] {
+            assert!(tables.draw(biome, &mut rng).is_some(), "{:?}", biome);
+        }
+    }
+
+    #[test]
+    fn same_seed_same_outcome() {
+        let tables = tables();
+        let resolve = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let encounter = tables.draw(Biome::Rock, &mut rng).unwrap();
+            encounter.resolve(&mut rng, &metrics(20), &Crew::default())
+        };
+        assert_eq!(resolve(9), resolve(9));
+    }
+
+    #[test]
+    fn shipped_tables_are_valid() {
+        tables().validate().unwrap();
+    }
+
+    #[test]
+    fn backwards_ranges_fail_validation() {
+        let tables = EncounterTables(HashMap::from([(
+            Biome::Rock,
+            vec![Encounter {
+                name: "Ore vein".to_string(),
+                text: String::new(),
+                weight: 1,
+                check: Check {
+                    dice: 1,
+                    skill: None,
+                    stat: None,
+                    difficulty: 1,
+                },
+                success: vec![Outcome::Parts { min: 5, max: 2 }],
+                failure: vec![],
+            }],
+        )]));
+        assert!(tables.validate().is_err());
+    }
+
+    #[test]
+    fn skills_and_ship_improve_the_odds() {
+        let encounter = Encounter {
+            name: "Cave-in".to_string(),
+            text: String::new(),
+            weight: 1,
+            check: Check {
+                dice: 2,
+                skill: Some(Skill::Engineering),
+                stat: Some(ShipStat::Armor),
+                difficulty: 10,
+            },
+            success: vec![],
+            failure: vec![Outcome::CrewLoss { count: 1 }],
+        };
+        let successes = |metrics: &ShipMetrics, crew: &Crew| {
+            let mut rng = StdRng::seed_from_u64(5);
+            (0..500)
+                .filter(|_| encounter.resolve(&mut rng, metrics, crew).success)
+                .count()
+        };
+        let green = Crew(vec![]);
+        assert!(successes(&metrics(60), &green) > successes(&metrics(0), &green));
+        assert!(successes(&metrics(0), &Crew::default()) > successes(&metrics(0), &green));
+
+        let mut crew = Crew::default();
+        let mut rng = StdRng::seed_from_u64(5);
+        let failed = (0..100)
+            .map(|_| encounter.resolve(&mut rng, &metrics(0), &crew))
+            .find(|r| !r.success)
+            .unwrap();
+        crew.apply(&failed);
+        assert_eq!(crew.0.len(), 2);
+    }
+}
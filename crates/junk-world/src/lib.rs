@@ -1,13 +1,114 @@
-mod poisson;
+mod asteroid;
+mod backdrop;
+mod cargo;
+mod chunk;
+mod config;
+mod encounter;
 mod stars;
+mod station;
 
-use bevy::{app::Startup, prelude::Plugin};
-use stars::starfield_startup_system;
+use asteroid::{drift_asteroids, mine_asteroids};
+use backdrop::{apply_backdrop, apply_background, parallax_nebula, spawn_nebula};
+use bevy::{
+    app::{PreStartup, Startup, Update},
+    asset::AssetApp,
+    prelude::{resource_changed, Condition, IntoSystemConfigs, Plugin},
+};
+use chunk::{init_chunk_materials, light_chunks, parts_changed, reset_chunks, stream_chunks};
+use config::{apply_starfield_config, load_starfield_config, StarfieldConfigLoader};
+use encounter::{
+    apply_encounter_tables, apply_explorations, explore_planets, load_encounter_tables,
+    EncounterTablesLoader,
+};
+use junk_unav::ChunkDeltas;
+use stars::{parallax_starfield, regenerate_starfield, starfield_startup_system};
+use station::loot_containers;
+
+pub use asteroid::{
+    Asteroid, AsteroidMined, BeltAngle, Collider, Drift, MineAsteroid, Rock, SystemAsteroids,
+};
+pub use backdrop::{Nebula, SystemBackdrop};
+pub use cargo::Cargo;
+pub use chunk::{
+    chunk_coord, chunk_seed, Chunk, ChunkItem, ChunkMaterials, LoadedChunks, OrbitingChunk,
+    CHUNK_SIZE,
+};
+pub use config::{Curve, StarfieldConfig, StarfieldConfigHandle};
+pub use encounter::{
+    Check, Crew, CrewMember, Effect, Encounter, EncounterTables, EncounterTablesHandle,
+    ExplorePlanet, Outcome, PlanetExplored, Resolution, ShipStat, Skill,
+};
+pub use stars::{FieldComponent, StarField, StarfieldMaterial, StarfieldSeed};
+pub use station::{
+    Container, LootContainer, Room, RoomKind, StationKind, StationLayout, StationStructure,
+    SystemStation, Tile,
+};
 
 pub struct WorldPlugin;
 
 impl Plugin for WorldPlugin {
     fn build(&self, app: &mut bevy::app::App) {
-        app.add_systems(Startup, starfield_startup_system);
+        app.init_resource::<Cargo>()
+            .init_resource::<StarfieldSeed>()
+            .init_resource::<StarfieldConfig>()
+            .init_asset::<StarfieldConfig>()
+            .init_asset_loader::<StarfieldConfigLoader>()
+            .add_systems(PreStartup, load_starfield_config)
+            .init_resource::<SystemBackdrop>()
+            .add_systems(Startup, (starfield_startup_system, spawn_nebula))
+            .add_systems(Update, apply_starfield_config)
+            .add_systems(
+                Update,
+                regenerate_starfield.after(apply_starfield_config).run_if(
+                    resource_changed::<StarfieldSeed>.or(resource_changed::<StarfieldConfig>),
+                ),
+            )
+            .add_systems(Update, parallax_starfield.after(regenerate_starfield))
+            .add_systems(
+                Update,
+                apply_backdrop.run_if(
+                    resource_changed::<SystemBackdrop>.or(resource_changed::<StarfieldSeed>),
+                ),
+            )
+            .add_systems(
+                Update,
+                apply_background.run_if(resource_changed::<SystemBackdrop>),
+            )
+            .add_systems(Update, parallax_nebula)
+            .init_resource::<SystemAsteroids>()
+            .init_resource::<BeltAngle>()
+            .init_resource::<ChunkDeltas>()
+            .add_systems(Update, drift_asteroids)
+            .add_event::<MineAsteroid>()
+            .add_event::<AsteroidMined>()
+            .add_systems(Update, mine_asteroids)
+            .init_resource::<SystemStation>()
+            .add_event::<LootContainer>()
+            .add_systems(Update, loot_containers)
+            .init_resource::<LoadedChunks>()
+            .add_systems(Startup, init_chunk_materials)
+            .add_systems(
+                Update,
+                reset_chunks.run_if(
+                    resource_changed::<SystemAsteroids>
+                        .or(resource_changed::<SystemStation>)
+                        .or(parts_changed),
+                ),
+            )
+            .add_systems(Update, stream_chunks.after(reset_chunks))
+            .add_systems(
+                Update,
+                light_chunks.run_if(resource_changed::<SystemBackdrop>),
+            )
+            .init_resource::<Crew>()
+            .init_resource::<EncounterTables>()
+            .init_asset::<EncounterTables>()
+            .init_asset_loader::<EncounterTablesLoader>()
+            .add_event::<ExplorePlanet>()
+            .add_event::<PlanetExplored>()
+            .add_systems(PreStartup, load_encounter_tables)
+            .add_systems(Update, apply_encounter_tables)
+            .add_systems(Update, explore_planets.after(apply_encounter_tables))
+            .add_systems(Update, apply_explorations.after(explore_planets));
     }
 }
@@ -1,73 +1,202 @@
 use bevy::{
-    color::Color,
-    math::I64Vec2,
-    prelude::{BuildChildren, ChildBuild, Commands, Component, InheritedVisibility, Transform},
-    sprite::Sprite,
+    asset::{Assets, Handle, RenderAssetUsages},
+    color::{Color, ColorToComponents, LinearRgba},
+    math::{Rect, Vec2},
+    prelude::{
+        BuildChildren, Camera2d, ChildBuild, Commands, Component, DespawnRecursiveExt,
+        DetectChanges, Entity, InheritedVisibility, Mesh, Mesh2d, Query, Res, ResMut, Resource,
+        Transform, With, Without,
+    },
+    render::mesh::{Indices, PrimitiveTopology},
+    sprite::{ColorMaterial, MeshMaterial2d},
 };
-use rand::Rng;
+use junk_procgen::PoissonSampler;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::config::StarfieldConfig;
+
+/// Seed the starfield is generated from, set it to the current system's seed
+/// so every system has its own backdrop.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StarfieldSeed(pub u64);
+
+/// Share of the camera's movement the nearest and farthest layers follow,
+/// 1.0 would be glued to the world and 0.0 to the camera.
+const NEAR_PARALLAX: f32 = 0.6;
+const FAR_PARALLAX: f32 = 0.05;
+
+/// Spreads layer indices out so neighbouring layers don't share a seed.
+const LAYER_SEED_MULTIPLIER: u64 = 0x9e37_79b9_7f4a_7c15;
 
 #[derive(Component)]
-pub struct StarField {}
+pub struct StarField {
+    /// What the field was generated from.
+    pub seed: u64,
+}
 
 #[derive(Component)]
 pub struct FieldComponent {
-    pub points: Vec<I64Vec2>,
     pub distance: f32,
+    /// Side length of the square the stars repeat over.
+    pub extent: f32,
+}
+
+impl FieldComponent {
+    /// How much of the camera's movement this layer follows, near layers
+    /// (higher `distance`, bigger and brighter stars) follow the most.
+    pub fn parallax(&self) -> f32 {
+        FAR_PARALLAX + (NEAR_PARALLAX - FAR_PARALLAX) * self.distance
+    }
 }
 
 #[derive(Clone)]
 pub struct Field {
-    points: Vec<I64Vec2>,
+    points: Vec<Vec2>,
     distance: f32,
+    extent: f32,
+    seed: u64,
+    size: f32,
+    size_jitter: f32,
+    color: Color,
 }
 
-impl Into<FieldComponent> for Field {
-    fn into(self) -> FieldComponent {
+impl From<Field> for FieldComponent {
+    fn from(field: Field) -> Self {
         FieldComponent {
-            points: self.points,
-            distance: self.distance,
+            distance: field.distance,
+            extent: field.extent,
         }
     }
 }
 
 impl Field {
-    pub fn generate(image_size: usize, distance: f32) -> Self {
-        let mut rng = rand::thread_rng();
+    /// A layer at `distance`, 0.0 being the farthest and 1.0 the nearest.
+    pub fn generate(config: &StarfieldConfig, distance: f32, seed: u64) -> Self {
+        let extent = config.extent as f32;
+        // a zero distance would never finish sampling
+        let min_dist = config.min_dist.at(distance).max(1.0);
+        // sampled in whole units over 0..extent and then centred, as layers
+        // always were, so a seed keeps its stars where they were
+        let centre = Vec2::splat((config.extent / 2) as f32);
+        let points = PoissonSampler::new(Rect::new(0.0, 0.0, extent, extent), min_dist)
+            .attempts(config.attempts)
+            .snapped()
+            .sample(seed);
         Self {
-            points: crate::poisson::sample(
-                image_size as isize,
-                image_size as isize,
-                200.0,
-                90,
-                rng.gen(),
-            ),
+            points: points.into_iter().map(|point| point - centre).collect(),
             distance,
+            extent: config.extent as f32,
+            seed,
+            size: config.size.at(distance),
+            size_jitter: config.size_jitter,
+            color: config.color.at(distance),
         }
     }
 }
 
-pub fn generate(image_size: usize, layers: usize) -> Vec<Field> {
+pub fn generate(config: &StarfieldConfig, seed: u64) -> Vec<Field> {
+    let layers = config.layers.max(1);
     let fields: Vec<Field> = (0..layers + 1)
-        .into_iter()
         .map(|layer| {
             let distance = layer as f32 / layers as f32;
-            Field::generate(image_size, distance)
+            let layer_seed = seed ^ (layer as u64 + 1).wrapping_mul(LAYER_SEED_MULTIPLIER);
+            Field::generate(config, distance, layer_seed)
         })
         .collect();
 
     fields
 }
 
-pub fn spawn(commands: &mut Commands, fields: Vec<Field>) {
+/// One star in a layer's mesh.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Star {
+    pub position: Vec2,
+    pub size: f32,
+    pub color: Color,
+}
+
+impl Field {
+    /// The stars of this layer, near layers get bigger and brighter stars.
+    pub fn stars(&self) -> Vec<Star> {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        self.points
+            .iter()
+            .map(|point| Star {
+                position: *point,
+                size: self.size * (1.0 + rng.gen_range(-1.0..=1.0) * self.size_jitter),
+                color: self.color,
+            })
+            .collect()
+    }
+
+    /// A single mesh with a coloured quad per star.
+    pub fn mesh(&self) -> Mesh {
+        let stars = self.stars();
+        let mut positions = Vec::with_capacity(stars.len() * 4);
+        let mut colors = Vec::with_capacity(stars.len() * 4);
+        let mut uvs = Vec::with_capacity(stars.len() * 4);
+        let mut indices = Vec::with_capacity(stars.len() * 6);
+        for (i, star) in stars.iter().enumerate() {
+            let half = star.size / 2.0;
+            let color = LinearRgba::from(star.color).to_f32_array();
+            for (corner, uv) in [
+                (Vec2::new(-half, -half), [0.0, 1.0]),
+                (Vec2::new(half, -half), [1.0, 1.0]),
+                (Vec2::new(half, half), [1.0, 0.0]),
+                (Vec2::new(-half, half), [0.0, 0.0]),
+            ] {
+                let vertex = star.position + corner;
+                positions.push([vertex.x, vertex.y, 0.0]);
+                colors.push(color);
+                uvs.push(uv);
+            }
+            let base = i as u32 * 4;
+            indices.extend([base, base + 1, base + 2, base, base + 2, base + 3]);
+        }
+
+        Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::RENDER_WORLD,
+        )
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, colors)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+        .with_inserted_indices(Indices::U32(indices))
+    }
+}
+
+/// One copy of a layer's mesh. Each layer is drawn as a 3x3 grid of tiles
+/// sharing the same mesh, which is moved around the camera to wrap.
+#[derive(Component)]
+pub struct StarTile;
+
+/// Tiles either side of the centre one.
+const TILE_RADIUS: i32 = 1;
+
+/// Material shared by every layer. Stars get their colour from the mesh,
+/// the material's colour tints the whole field.
+#[derive(Resource)]
+pub struct StarfieldMaterial(pub Handle<ColorMaterial>);
+
+pub fn spawn(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    material: &Handle<ColorMaterial>,
+    fields: Vec<Field>,
+    seed: u64,
+) {
     commands
         .spawn((
-            StarField {},
+            StarField { seed },
             InheritedVisibility::default(),
             Transform::default(),
         ))
         .with_children(|entity| {
             for field in fields {
-                let field_component: FieldComponent = field.clone().into();
+                let mesh = meshes.add(field.mesh());
+                let distance = field.distance;
+                let extent = field.extent;
+                let field_component: FieldComponent = field.into();
                 entity
                     .spawn((
                         field_component,
@@ -75,27 +204,169 @@ pub fn spawn(commands: &mut Commands, fields: Vec<Field>) {
                         InheritedVisibility::default(),
                     ))
                     .with_children(|entity| {
-                        let distance = field.distance;
-                        let size = distance * 4.0;
-                        let custom_size = Some(bevy::math::Vec2 { x: size, y: size });
-                        let color = Color::srgba(1.0, 0.95, 1.0 - (distance / 4.0), distance);
-                        for point in field.points {
-                            entity.spawn((
-                                Sprite {
-                                    custom_size,
-                                    color,
-                                    ..Default::default()
-                                },
-                                Transform::from_xyz(point.x as f32, point.y as f32, distance),
-                                InheritedVisibility::default(),
-                            ));
+                        for x in -TILE_RADIUS..=TILE_RADIUS {
+                            for y in -TILE_RADIUS..=TILE_RADIUS {
+                                entity.spawn((
+                                    StarTile,
+                                    Mesh2d(mesh.clone()),
+                                    MeshMaterial2d(material.clone()),
+                                    Transform::from_xyz(
+                                        x as f32 * extent,
+                                        y as f32 * extent,
+                                        distance,
+                                    ),
+                                ));
+                            }
                         }
                     });
             }
         });
 }
 
-pub fn starfield_startup_system(mut commands: Commands) {
-    let fields = generate(4096, 15);
-    spawn(&mut commands, fields);
+/// Generates the starfield from `StarfieldSeed`, which the game should set
+/// before `Startup` to avoid generating it twice.
+pub fn starfield_startup_system(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    config: Res<StarfieldConfig>,
+    seed: Res<StarfieldSeed>,
+) {
+    let material = materials.add(ColorMaterial::default());
+    let fields = generate(&config, seed.0);
+    spawn(&mut commands, &mut meshes, &material, fields, seed.0);
+    commands.insert_resource(StarfieldMaterial(material));
+}
+
+/// Swaps the starfield out when the seed changes, i.e. on arriving in
+/// another system, or when the config is reloaded.
+pub fn regenerate_starfield(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    material: Res<StarfieldMaterial>,
+    config: Res<StarfieldConfig>,
+    seed: Res<StarfieldSeed>,
+    starfields: Query<(Entity, &StarField)>,
+) {
+    // the startup system already used the initial config
+    let config_changed = config.is_changed() && !config.is_added();
+    if !config_changed && starfields.iter().any(|(_, field)| field.seed == seed.0) {
+        return;
+    }
+    for (starfield, _) in starfields.iter() {
+        commands.entity(starfield).despawn_recursive();
+    }
+    let fields = generate(&config, seed.0);
+    spawn(&mut commands, &mut meshes, &material.0, fields, seed.0);
+}
+
+/// Rounds `value` to the nearest whole multiple of `extent`.
+fn snap(value: f32, extent: f32) -> f32 {
+    (value / extent).round() * extent
+}
+
+/// Offsets each layer against the camera so far layers drift slower than
+/// near ones, and moves its tiles in whole extents to keep the camera
+/// surrounded so the field never ends.
+pub fn parallax_starfield(
+    cameras: Query<&Transform, (With<Camera2d>, Without<FieldComponent>)>,
+    mut fields: Query<(&FieldComponent, &mut Transform), Without<Camera2d>>,
+) {
+    let Ok(camera) = cameras.get_single() else {
+        return;
+    };
+    let camera = camera.translation.truncate();
+
+    for (field, mut transform) in fields.iter_mut() {
+        let offset = camera * (1.0 - field.parallax());
+        // where the camera is in the layer's own coordinates
+        let centre = camera - offset;
+        let wrapped =
+            offset + Vec2::new(snap(centre.x, field.extent), snap(centre.y, field.extent));
+        if transform.translation.truncate() != wrapped {
+            transform.translation.x = wrapped.x;
+            transform.translation.y = wrapped.y;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Curve;
+
+    #[test]
+    fn layers_snap_to_whole_tiles() {
+        assert_eq!(snap(100.0, 4096.0), 0.0);
+        assert_eq!(snap(3000.0, 4096.0), 4096.0);
+        assert_eq!(snap(-10_000.0, 4096.0), -8192.0);
+    }
+
+    #[test]
+    fn one_quad_per_star() {
+        let config = StarfieldConfig {
+            extent: 512,
+            ..StarfieldConfig::default()
+        };
+        let field = Field::generate(&config, 0.5, 1);
+        let mesh = field.mesh();
+        assert_eq!(mesh.count_vertices(), field.points.len() * 4);
+        assert_eq!(mesh.indices().unwrap().len(), field.points.len() * 6);
+    }
+    #[test]
+    fn same_seed_same_stars() {
+        let config = StarfieldConfig {
+            layers: 3,
+            extent: 512,
+            ..StarfieldConfig::default()
+        };
+        let a = generate(&config, 7);
+        let b = generate(&config, 7);
+        let c = generate(&config, 8);
+        for (a, b) in a.iter().zip(b.iter()) {
+            assert_eq!(a.points, b.points);
+        }
+        assert_ne!(a[0].points, c[0].points);
+        // layers don't just repeat each other
+        assert_ne!(a[0].points, a[1].points);
+    }
+
+    #[test]
+    fn far_layers_are_denser() {
+        let config = StarfieldConfig {
+            layers: 1,
+            extent: 1024,
+            min_dist: Curve(vec![(0.0, 120.0), (1.0, 320.0)]),
+            ..StarfieldConfig::default()
+        };
+        let fields = generate(&config, 3);
+        assert!(fields[0].points.len() > fields[1].points.len());
+    }
+
+    /// Stars per layer for seed 11 with the default config, as the integer
+    /// Poisson sampler the starfield started out with laid them out.
+    const OLD_STAR_COUNTS: [usize; 16] = [
+        283, 292, 288, 293, 290, 292, 286, 293, 296, 291, 290, 289, 286, 288, 290, 289,
+    ];
+
+    /// The first star of each of those layers, as x, y pairs.
+    const OLD_FIRST_STARS: [f32; 32] = [
+        95.0, 980.0, 91.0, 1335.0, 1459.0, -795.0, 885.0, 1875.0, -1800.0, -469.0, 1709.0, 2004.0,
+        -63.0, -1674.0, 1529.0, -306.0, -2004.0, 1394.0, -129.0, 1911.0, 457.0, -1998.0, -304.0,
+        1186.0, -1555.0, 696.0, -1569.0, 1724.0, -605.0, 450.0, 1658.0, -518.0,
+    ];
+
+    #[test]
+    fn default_config_keeps_the_old_layout() {
+        let config = StarfieldConfig::default();
+        for (layer, field) in generate(&config, 11).iter().enumerate() {
+            let distance = layer as f32 / 15.0;
+            assert_eq!(field.points.len(), OLD_STAR_COUNTS[layer]);
+            assert_eq!(
+                field.points[0],
+                Vec2::new(OLD_FIRST_STARS[layer * 2], OLD_FIRST_STARS[layer * 2 + 1])
+            );
+            assert!(field.stars().iter().all(|star| star.size == distance * 4.0));
+        }
+    }
 }
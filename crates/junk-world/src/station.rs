@@ -0,0 +1,735 @@
+use std::collections::{BTreeMap, HashSet, VecDeque};
+
+use bevy::{
+    asset::{Assets, RenderAssetUsages},
+    color::{Color, ColorToComponents, LinearRgba},
+    math::{primitives::Rectangle, IVec2, UVec2, Vec2},
+    prelude::{
+        ChildBuild, ChildBuilder, Component, Entity, Event, EventReader, Mesh, Mesh2d, Query, Res,
+        ResMut, Resource, Transform,
+    },
+    render::mesh::{Indices, PrimitiveTopology},
+    sprite::MeshMaterial2d,
+};
+use junk_ship::{Automata, CellType, Direction, PartInfo, PartType, Ship};
+use junk_unav::{ChunkDelta, ChunkDeltas, LootKind, WreckSite};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::{
+    asteroid::Collider,
+    cargo::Cargo,
+    chunk::{chunk_coord, ChunkItem, ChunkMaterials},
+};
+
+/// World units per station tile.
+const TILE_SIZE: f32 = 16.0;
+
+/// Side length of a container.
+const CONTAINER_SIZE: f32 = TILE_SIZE * 0.7;
+
+/// Tiles per side of a module, the square each room sits in.
+const MODULE_SIZE: i32 = 12;
+
+/// Modules the hull automata grows in.
+const STATION_GRID: UVec2 = UVec2::new(15, 15);
+
+const MIN_WALK_LENGTH: usize = 16;
+const MAX_WALK_LENGTH: usize = 32;
+const AUTOMATA_ITERATIONS: usize = 7;
+const MIN_ROOMS: usize = 6;
+const MAX_GROWTH_ATTEMPTS: usize = 8;
+
+/// Where the station sits in the system scene.
+const STATION_POSITION: Vec2 = Vec2::new(-900.0, 300.0);
+
+const CONTAINER_COLOR: Color = Color::srgb(0.85, 0.55, 0.2);
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Down,
+    Direction::Left,
+    Direction::Right,
+];
+
+/// Mixed into the system seed so the station differs from the asteroids.
+const STATION_SEED_SALT: u64 = 0x7374_6174;
+
+type Cell = (i32, i32);
+
+fn step(cell: Cell, direction: Direction, distance: i32) -> Cell {
+    let offset = direction.to_vec2();
+    (
+        cell.0 + offset.x as i32 * distance,
+        cell.1 + offset.y as i32 * distance,
+    )
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StationKind {
+    /// Crewed, its cargo isn't up for grabs.
+    Intact,
+    /// Abandoned and holed, with containers left to loot.
+    Derelict,
+}
+
+/// The station in the system the player is in, if there is one. The game
+/// fills this in on arrival.
+#[derive(Resource, Clone, Debug, Default, PartialEq)]
+pub struct SystemStation {
+    pub seed: u64,
+    pub kind: Option<StationKind>,
+    /// Loot tables containers are filled from.
+    pub wrecks: Vec<WreckSite>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RoomKind {
+    Core,
+    Habitat,
+    Storage,
+    Hangar,
+    Reactor,
+}
+
+impl RoomKind {
+    /// The room a hull cell holds. The cockpit becomes the core, the
+    /// automata's engine row docking hangars, and the rest follow the part
+    /// picked for the cell.
+    fn of(cell: CellType, part: &PartInfo) -> Self {
+        match (cell, &part.properties.part_type) {
+            (CellType::Cockpit, _) => RoomKind::Core,
+            (CellType::Engine, _) => RoomKind::Hangar,
+            (_, PartType::Cargo { .. }) => RoomKind::Storage,
+            (_, PartType::Engine { .. }) => RoomKind::Reactor,
+            _ => RoomKind::Habitat,
+        }
+    }
+
+    fn size(&self, rng: &mut StdRng) -> (i32, i32) {
+        match self {
+            RoomKind::Core => (7, 7),
+            RoomKind::Habitat => (rng.gen_range(3..=5), rng.gen_range(3..=5)),
+            RoomKind::Storage => (rng.gen_range(4..=6), rng.gen_range(3..=4)),
+            RoomKind::Hangar => (rng.gen_range(8..=10), rng.gen_range(6..=8)),
+            RoomKind::Reactor => (5, 5),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Room {
+    pub kind: RoomKind,
+    pub min: Cell,
+    pub size: (i32, i32),
+}
+
+impl Room {
+    pub fn contains(&self, cell: Cell) -> bool {
+        (self.min.0..self.min.0 + self.size.0).contains(&cell.0)
+            && (self.min.1..self.min.1 + self.size.1).contains(&cell.1)
+    }
+
+    /// A room of `size` centred in the module at `module`.
+    fn centred(kind: RoomKind, size: (i32, i32), module: Cell) -> Self {
+        let centre = module_centre(module);
+        Room {
+            kind,
+            min: (centre.0 - size.0 / 2, centre.1 - size.1 / 2),
+            size,
+        }
+    }
+
+    fn cells(&self) -> impl Iterator<Item = Cell> + '_ {
+        (self.min.0..self.min.0 + self.size.0)
+            .flat_map(move |x| (self.min.1..self.min.1 + self.size.1).map(move |y| (x, y)))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tile {
+    Floor,
+    Wall,
+    /// Hull blown open to space.
+    Breach,
+}
+
+/// A lootable container. Taking its contents empties it.
+#[derive(Component, Clone, Debug, PartialEq)]
+pub struct Container {
+    pub loot: Vec<(LootKind, u32)>,
+}
+
+impl Container {
+    pub fn take(&mut self) -> Vec<(LootKind, u32)> {
+        std::mem::take(&mut self.loot)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.loot.is_empty()
+    }
+}
+
+/// One cell of the hull the automata grew, with the part picked for it.
+/// Its connectors decide which neighbours it gets a corridor to.
+#[derive(Clone, Debug, PartialEq)]
+struct Module {
+    kind: RoomKind,
+    connectors: Vec<Direction>,
+}
+
+impl Module {
+    fn new(cell: CellType, part: &PartInfo) -> Self {
+        Self {
+            kind: RoomKind::of(cell, part),
+            connectors: DIRECTIONS
+                .into_iter()
+                .filter(|direction| connects(part, *direction))
+                .collect(),
+        }
+    }
+}
+
+fn connects(part: &PartInfo, direction: Direction) -> bool {
+    part.connector_points
+        .values()
+        .any(|directions| directions.contains(&direction))
+}
+
+fn part_openings(part: &PartInfo) -> usize {
+    DIRECTIONS
+        .into_iter()
+        .filter(|direction| connects(part, *direction))
+        .count()
+}
+
+/// Rooms joined by corridors. The hull is grown by the same automata as
+/// ships, one room per cell, and rooms are joined where the parts picked
+/// for neighbouring cells have connectors facing each other.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StationLayout {
+    pub kind: StationKind,
+    pub rooms: Vec<Room>,
+    pub tiles: BTreeMap<Cell, Tile>,
+    pub containers: Vec<(Cell, Container)>,
+}
+
+impl StationLayout {
+    /// The layout for the current system, the same every visit. `None` when
+    /// there's no station, or no parts to build one from.
+    pub fn generate(station: &SystemStation, parts: &HashSet<PartInfo>) -> Option<Self> {
+        let kind = station.kind?;
+        let mut rng = StdRng::seed_from_u64(station.seed ^ STATION_SEED_SALT);
+
+        let modules = Self::modules(&mut rng, parts)?;
+        let (rooms, corridors) = Self::rooms(&mut rng, &modules);
+        let mut tiles = BTreeMap::new();
+        for cell in rooms.iter().flat_map(|room| room.cells()).chain(corridors) {
+            tiles.insert(cell, Tile::Floor);
+        }
+        if kind == StationKind::Derelict {
+            Self::breach(&mut rng, &mut tiles);
+        }
+        Self::walls(&mut tiles);
+
+        let containers = match kind {
+            StationKind::Derelict => Self::containers(&mut rng, &rooms, &tiles, &station.wrecks),
+            StationKind::Intact => Vec::new(),
+        };
+
+        Some(Self {
+            kind,
+            rooms,
+            tiles,
+            containers,
+        })
+    }
+
+    /// Grows the hull, then fills it outwards from the core. A cell gets a
+    /// part the way ships pick them, from the parts whose connectors only
+    /// face neighbouring cells, and it has to connect back to the cell it
+    /// was reached from. Cells nothing connects to are left empty.
+    fn modules(rng: &mut StdRng, parts: &HashSet<PartInfo>) -> Option<BTreeMap<Cell, Module>> {
+        let cockpit = parts
+            .iter()
+            .filter(|part| matches!(part.properties.part_type, PartType::Cockpit { .. }))
+            .min_by_key(|part| part.id)?;
+
+        // hulls that close off next to the core make poor stations, regrow
+        // a few times and keep the biggest
+        let mut best = BTreeMap::new();
+        for _ in 0..MAX_GROWTH_ATTEMPTS {
+            let modules = Self::grow(rng, parts, cockpit);
+            if modules.len() > best.len() {
+                best = modules;
+            }
+            if best.len() >= MIN_ROOMS {
+                break;
+            }
+        }
+        Some(best)
+    }
+
+    fn grow(
+        rng: &mut StdRng,
+        parts: &HashSet<PartInfo>,
+        cockpit: &PartInfo,
+    ) -> BTreeMap<Cell, Module> {
+        let walk_length = rng.gen_range(MIN_WALK_LENGTH..=MAX_WALK_LENGTH);
+        let mut automata =
+            Automata::new(StdRng::seed_from_u64(rng.gen()), STATION_GRID, walk_length);
+        automata.run(AUTOMATA_ITERATIONS);
+
+        // the automata grows down the grid from the cockpit, flip it so the
+        // core sits at the origin with the hull hanging below it
+        let centre = ((STATION_GRID.x / 2) as i32, (STATION_GRID.y / 2) as i32);
+        let cells: BTreeMap<Cell, CellType> = automata
+            .get_non_empty()
+            .into_iter()
+            .map(|((x, y), cell)| ((x as i32 - centre.0, centre.1 - y as i32), cell))
+            .collect();
+
+        let mut modules = BTreeMap::new();
+        modules.insert((0, 0), Module::new(CellType::Cockpit, cockpit));
+        let mut frontier = VecDeque::from([(0, 0)]);
+        while let Some(position) = frontier.pop_front() {
+            for direction in modules[&position].connectors.clone() {
+                let next = step(position, direction, 1);
+                let Some(cell) = cells.get(&next) else {
+                    continue;
+                };
+                if modules.contains_key(&next) {
+                    continue;
+                }
+
+                let neighbours = DIRECTIONS
+                    .into_iter()
+                    .filter(|direction| cells.contains_key(&step(next, *direction, 1)))
+                    .collect();
+                let candidates: Vec<PartInfo> =
+                    Ship::find_parts_with_only_directions(parts, neighbours)
+                        .into_iter()
+                        .filter(|part| connects(part, direction.invert()))
+                        .collect();
+                // lean towards parts that open up more of the hull, so
+                // stations don't stop short at the first dead end
+                if let Ok(part) = candidates.choose_weighted(rng, |part| part_openings(part).pow(2))
+                {
+                    modules.insert(next, Module::new(*cell, part));
+                    frontier.push_back(next);
+                }
+            }
+        }
+        modules
+    }
+
+    /// Whether `from` has a corridor to its neighbour in `direction`: both
+    /// parts need a connector facing the other.
+    fn joined(modules: &BTreeMap<Cell, Module>, from: Cell, direction: Direction) -> bool {
+        let Some(module) = modules.get(&from) else {
+            return false;
+        };
+        module.connectors.contains(&direction)
+            && modules
+                .get(&step(from, direction, 1))
+                .is_some_and(|other| other.connectors.contains(&direction.invert()))
+    }
+
+    /// A room in every module, with a corridor along each join.
+    fn rooms(rng: &mut StdRng, modules: &BTreeMap<Cell, Module>) -> (Vec<Room>, Vec<Cell>) {
+        let mut rooms = Vec::new();
+        let mut corridors = Vec::new();
+        for (position, module) in modules {
+            rooms.push(Room::centred(module.kind, module.kind.size(rng), *position));
+            // each join once, from its lower or left end
+            for direction in [Direction::Up, Direction::Right] {
+                if Self::joined(modules, *position, direction) {
+                    let start = module_centre(*position);
+                    corridors.extend((0..=MODULE_SIZE).map(|i| step(start, direction, i)));
+                }
+            }
+        }
+
+        (rooms, corridors)
+    }
+
+    /// Blasts holes in the hull, then lets them spread a step the way the
+    /// ship automata grow hull, so the damage looks torn rather than round.
+    fn breach(rng: &mut StdRng, tiles: &mut BTreeMap<Cell, Tile>) {
+        let floor: Vec<Cell> = tiles.keys().copied().collect();
+        let hits = rng.gen_range(3..=8);
+        for _ in 0..hits {
+            let centre = *floor.choose(rng).unwrap();
+            let radius = rng.gen_range(1..=3);
+            for dx in -radius..=radius {
+                for dy in -radius..=radius {
+                    if dx * dx + dy * dy <= radius * radius {
+                        if let Some(tile) = tiles.get_mut(&(centre.0 + dx, centre.1 + dy)) {
+                            *tile = Tile::Breach;
+                        }
+                    }
+                }
+            }
+        }
+
+        let spread: Vec<Cell> = tiles
+            .iter()
+            .filter(|(_, tile)| **tile == Tile::Floor)
+            .map(|(cell, _)| *cell)
+            .filter(|cell| {
+                let breached = (-1..=1)
+                    .flat_map(|dx| (-1..=1).map(move |dy| (cell.0 + dx, cell.1 + dy)))
+                    .filter(|neighbour| tiles.get(neighbour) == Some(&Tile::Breach))
+                    .count();
+                breached >= 4 && rng.gen_bool(0.5)
+            })
+            .collect();
+        for cell in spread {
+            tiles.insert(cell, Tile::Breach);
+        }
+    }
+
+    /// Surrounds floor with walls. Breaches are open to space.
+    fn walls(tiles: &mut BTreeMap<Cell, Tile>) {
+        let walls: Vec<Cell> = tiles
+            .iter()
+            .filter(|(_, tile)| **tile == Tile::Floor)
+            .flat_map(|(cell, _)| {
+                (-1..=1).flat_map(move |dx| (-1..=1).map(move |dy| (cell.0 + dx, cell.1 + dy)))
+            })
+            .filter(|cell| !tiles.contains_key(cell))
+            .collect();
+        for cell in walls {
+            tiles.insert(cell, Tile::Wall);
+        }
+    }
+
+    /// Storage rooms hold a few containers, other rooms the odd one. Loot
+    /// comes from the system's wreck tables.
+    fn containers(
+        rng: &mut StdRng,
+        rooms: &[Room],
+        tiles: &BTreeMap<Cell, Tile>,
+        wrecks: &[WreckSite],
+    ) -> Vec<(Cell, Container)> {
+        let mut containers: Vec<(Cell, Container)> = Vec::new();
+        for room in rooms {
+            let count = match room.kind {
+                RoomKind::Storage => rng.gen_range(2..=4),
+                RoomKind::Core => 0,
+                _ if rng.gen_bool(0.25) => 1,
+                _ => 0,
+            };
+            let mut cells: Vec<Cell> = room
+                .cells()
+                .filter(|cell| tiles.get(cell) == Some(&Tile::Floor))
+                .collect();
+            cells.shuffle(rng);
+
+            for cell in cells.into_iter().take(count) {
+                let loot = match wrecks.choose(rng) {
+                    Some(wreck) => (0..rng.gen_range(1..=3))
+                        .filter_map(|_| wreck.roll(rng))
+                        .collect(),
+                    None => Vec::new(),
+                };
+                containers.push((cell, Container { loot }));
+            }
+        }
+        containers
+    }
+
+    fn mesh(&self) -> Mesh {
+        let (floor, wall) = match self.kind {
+            StationKind::Intact => (Color::srgb(0.22, 0.24, 0.28), Color::srgb(0.5, 0.55, 0.62)),
+            StationKind::Derelict => (Color::srgb(0.14, 0.13, 0.13), Color::srgb(0.38, 0.33, 0.3)),
+        };
+        let mut positions = Vec::new();
+        let mut colors = Vec::new();
+        let mut indices = Vec::new();
+        for (cell, tile) in &self.tiles {
+            let color = match tile {
+                Tile::Floor => floor,
+                Tile::Wall => wall,
+                Tile::Breach => continue,
+            };
+            let color = LinearRgba::from(color).to_f32_array();
+            let base = positions.len() as u32;
+            let min = cell_position(*cell) - Vec2::splat(TILE_SIZE / 2.0);
+            for corner in [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)] {
+                let vertex = min + Vec2::new(corner.0, corner.1) * TILE_SIZE;
+                positions.push([vertex.x, vertex.y, 0.0]);
+                colors.push(color);
+            }
+            indices.extend([base, base + 1, base + 2, base, base + 2, base + 3]);
+        }
+
+        Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::RENDER_WORLD,
+        )
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, colors)
+        .with_inserted_indices(Indices::U32(indices))
+    }
+}
+
+/// The tile in the middle of a module.
+fn module_centre(module: Cell) -> Cell {
+    (module.0 * MODULE_SIZE, module.1 * MODULE_SIZE)
+}
+
+/// Centre of a cell, relative to the station.
+fn cell_position(cell: Cell) -> Vec2 {
+    Vec2::new(cell.0 as f32, cell.1 as f32) * TILE_SIZE
+}
+
+/// Marks everything spawned for the current station.
+#[derive(Component)]
+pub struct StationStructure;
+
+/// A square container with its colour in the vertices, so the chunk
+/// material lights it along with the hull.
+pub(crate) fn container_mesh() -> Mesh {
+    let mesh = Mesh::from(Rectangle::new(CONTAINER_SIZE, CONTAINER_SIZE));
+    let count = mesh.count_vertices();
+    mesh.with_inserted_attribute(
+        Mesh::ATTRIBUTE_COLOR,
+        vec![LinearRgba::from(CONTAINER_COLOR).to_f32_array(); count],
+    )
+}
+
+/// The chunk the station is spawned with.
+pub(crate) fn station_chunk() -> IVec2 {
+    chunk_coord(STATION_POSITION)
+}
+
+/// Spawns the station and its containers. Containers that were looted
+/// before come back empty.
+pub(crate) fn spawn_station(
+    parent: &mut ChildBuilder,
+    meshes: &mut Assets<Mesh>,
+    materials: &ChunkMaterials,
+    layout: StationLayout,
+    delta: Option<&ChunkDelta>,
+) {
+    parent.spawn((
+        StationStructure,
+        Mesh2d(meshes.add(layout.mesh())),
+        MeshMaterial2d(materials.vertex.clone()),
+        Transform::from_translation(STATION_POSITION.extend(0.2)),
+    ));
+
+    let half = CONTAINER_SIZE / 2.0;
+    for (index, (cell, mut container)) in layout.containers.into_iter().enumerate() {
+        if delta.is_some_and(|delta| delta.emptied.contains(&index)) {
+            container.take();
+        }
+        parent.spawn((
+            StationStructure,
+            ChunkItem {
+                coord: station_chunk(),
+                index,
+            },
+            Mesh2d(materials.container_mesh.clone()),
+            MeshMaterial2d(materials.vertex.clone()),
+            Transform::from_translation((STATION_POSITION + cell_position(cell)).extend(0.3)),
+            Collider {
+                radius: half * std::f32::consts::SQRT_2,
+                polygon: vec![
+                    Vec2::new(-half, -half),
+                    Vec2::new(half, -half),
+                    Vec2::new(half, half),
+                    Vec2::new(-half, half),
+                ],
+            },
+            container,
+        ));
+    }
+}
+
+/// Asks for a container to be emptied into the cargo hold.
+#[derive(Event, Clone, Debug)]
+pub struct LootContainer {
+    pub container: Entity,
+}
+
+/// Empties containers into the cargo hold, and remembers they were so they
+/// stay empty when the station loads again.
+pub(crate) fn loot_containers(
+    mut requests: EventReader<LootContainer>,
+    mut containers: Query<(&mut Container, &ChunkItem)>,
+    station: Res<SystemStation>,
+    mut deltas: ResMut<ChunkDeltas>,
+    mut cargo: ResMut<Cargo>,
+) {
+    for request in requests.read() {
+        let Ok((mut container, item)) = containers.get_mut(request.container) else {
+            continue;
+        };
+        if container.is_empty() {
+            continue;
+        }
+        for (kind, amount) in container.take() {
+            cargo.add_loot(kind, amount);
+        }
+        deltas
+            .entry(station.seed, item.coord)
+            .emptied
+            .insert(item.index);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use junk_ship::Parts;
+    use junk_unav::{LootEntry, ResourceKind};
+
+    fn parts() -> HashSet<PartInfo> {
+        Parts::load_parts_from_bytes(include_bytes!("../../junk/assets/parts/stock.ron")).parts
+    }
+
+    fn station(seed: u64, kind: StationKind) -> SystemStation {
+        SystemStation {
+            seed,
+            kind: Some(kind),
+            wrecks: vec![WreckSite {
+                name: "Wreck".to_string(),
+                loot: vec![LootEntry {
+                    kind: LootKind::Resource(ResourceKind::Iron),
+                    weight: 1,
+                    min: 5,
+                    max: 10,
+                }],
+            }],
+        }
+    }
+
+    #[test]
+    fn same_seed_same_station() {
+        let parts = parts();
+        let a = StationLayout::generate(&station(7, StationKind::Derelict), &parts);
+        assert!(a.is_some());
+        assert_eq!(
+            a,
+            StationLayout::generate(&station(7, StationKind::Derelict), &parts)
+        );
+        assert!(StationLayout::generate(&SystemStation::default(), &parts).is_none());
+        // no parts, nothing to build from
+        assert!(
+            StationLayout::generate(&station(7, StationKind::Intact), &HashSet::new()).is_none()
+        );
+    }
+
+    #[test]
+    fn intact_stations_are_connected() {
+        let parts = parts();
+        for seed in 0..20 {
+            let layout =
+                StationLayout::generate(&station(seed, StationKind::Intact), &parts).unwrap();
+            assert!(layout.rooms.len() > 1);
+            assert!(layout.containers.is_empty());
+
+            let floor: Vec<Cell> = layout
+                .tiles
+                .iter()
+                .filter(|(_, tile)| **tile == Tile::Floor)
+                .map(|(cell, _)| *cell)
+                .collect();
+            let mut seen = vec![(0, 0)];
+            let mut frontier = vec![(0, 0)];
+            while let Some(cell) = frontier.pop() {
+                for direction in [
+                    Direction::Up,
+                    Direction::Down,
+                    Direction::Left,
+                    Direction::Right,
+                ] {
+                    let next = step(cell, direction, 1);
+                    if floor.contains(&next) && !seen.contains(&next) {
+                        seen.push(next);
+                        frontier.push(next);
+                    }
+                }
+            }
+            assert_eq!(seen.len(), floor.len(), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn derelicts_have_breaches_and_loot() {
+        let layout = StationLayout::generate(&station(3, StationKind::Derelict), &parts()).unwrap();
+        assert!(layout.tiles.values().any(|tile| *tile == Tile::Breach));
+        assert!(!layout.containers.is_empty());
+        for (cell, container) in &layout.containers {
+            assert_eq!(layout.tiles.get(cell), Some(&Tile::Floor));
+            assert!(!container.is_empty());
+        }
+    }
+
+    #[test]
+    fn rooms_join_where_connectors_meet() {
+        let parts = parts();
+        for seed in 0..20 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let modules = StationLayout::modules(&mut rng, &parts).unwrap();
+            assert_eq!(modules[&(0, 0)].kind, RoomKind::Core);
+            let (rooms, corridors) = StationLayout::rooms(&mut rng, &modules);
+            assert!(rooms.len() > 1, "seed {}", seed);
+
+            // the tile halfway between two rooms is corridor exactly when
+            // both their parts connect towards each other
+            for room in &rooms {
+                let module = (
+                    (room.min.0 + room.size.0 / 2) / MODULE_SIZE,
+                    (room.min.1 + room.size.1 / 2) / MODULE_SIZE,
+                );
+                for direction in [Direction::Up, Direction::Right] {
+                    let halfway = step(module_centre(module), direction, MODULE_SIZE / 2);
+                    assert_eq!(
+                        corridors.contains(&halfway),
+                        StationLayout::joined(&modules, module, direction),
+                        "seed {}",
+                        seed
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn looted_containers_stay_empty() {
+        use bevy::prelude::{App, Update};
+
+        let mut app = App::new();
+        app.init_resource::<Cargo>()
+            .insert_resource(station(3, StationKind::Derelict))
+            .init_resource::<ChunkDeltas>()
+            .add_event::<LootContainer>()
+            .add_systems(Update, loot_containers);
+        let container = app
+            .world_mut()
+            .spawn((
+                Container {
+                    loot: vec![(LootKind::Resource(ResourceKind::Iron), 7)],
+                },
+                ChunkItem {
+                    coord: IVec2::ZERO,
+                    index: 2,
+                },
+            ))
+            .id();
+        for _ in 0..2 {
+            app.world_mut().send_event(LootContainer { container });
+        }
+        app.update();
+
+        assert_eq!(
+            app.world().resource::<Cargo>().resource(ResourceKind::Iron),
+            7
+        );
+        assert!(app.world().get::<Container>(container).unwrap().is_empty());
+        let deltas = app.world().resource::<ChunkDeltas>();
+        assert!(deltas.get(3, IVec2::ZERO).unwrap().emptied.contains(&2));
+    }
+}
@@ -1,7 +1,16 @@
 use bevy::prelude::*;
-use junk_ship::{ShipPlugin, SpawnShipEvent};
-use junk_unav::{ToggleUNav, UNavPlugin};
-use junk_world::WorldPlugin;
+use junk_ship::{
+    EncounterStrength, LocalFactions, PlayerShip, ShipLighting, ShipPlugin, SpawnShipEvent,
+};
+use junk_unav::{
+    ChunkDeltas, CurrentSystem, FleetEncounter, GalaxyShape, GalaxySize, GoalEvent, GoalKind,
+    QuestCompleted, SystemComponents, SystemContents, ToggleUNav, UNav, UNavPlugin,
+};
+use junk_world::{
+    Asteroid, AsteroidMined, Cargo, Container, ExplorePlanet, LootContainer, MineAsteroid,
+    PlanetExplored, StarfieldSeed, StationKind, SystemAsteroids, SystemBackdrop, SystemStation,
+    WorldPlugin,
+};
 
 #[derive(Resource, Clone)]
 pub enum Focus {
@@ -19,12 +28,43 @@ fn main() {
     App::new()
         .insert_resource(Focus::Game)
         .add_plugins(DefaultPlugins.set(ImagePlugin::default_nearest()))
-        .add_plugins(UNavPlugin::generate(19940131))
+        .add_plugins(
+            UNavPlugin::generate(19940131, GalaxyShape::Square, GalaxySize::Small)
+                .with_goal(GoalKind::DeliverMessage),
+        )
         .add_plugins(ShipPlugin)
         .add_plugins(WorldPlugin)
         .add_event::<FocusChanged>()
         .add_systems(Update, focus_systems)
         .add_systems(Update, on_focus_changed)
+        // seeded before the starfield is first generated, so it only is once
+        .add_systems(PreStartup, (sync_starfield_seed, load_chunk_deltas))
+        .add_systems(
+            Update,
+            (
+                sync_encounter_strength,
+                sync_local_factions,
+                sync_starfield_seed,
+                sync_backdrop,
+                sync_asteroids,
+                sync_station,
+            )
+                .run_if(resource_changed::<CurrentSystem>),
+        )
+        .add_systems(
+            Update,
+            sync_ship_lighting.run_if(resource_changed::<SystemBackdrop>),
+        )
+        .add_systems(Update, on_goal_event)
+        .add_systems(Update, on_quest_completed)
+        .add_systems(Update, on_fleet_encounter)
+        .add_systems(Update, (mine_nearest_asteroid, on_asteroid_mined))
+        .add_systems(Update, (land_on_planet, on_planet_explored))
+        .add_systems(Update, loot_nearest_container)
+        .add_systems(
+            Update,
+            save_chunk_deltas.run_if(resource_changed::<ChunkDeltas>),
+        )
         .run();
 }
 
@@ -66,3 +106,286 @@ fn on_focus_changed(
         }
     }
 }
+
+// ships met in a system get tougher the further it is from the start
+fn sync_encounter_strength(
+    unav: Res<UNav>,
+    current_system: Res<CurrentSystem>,
+    mut encounter_strength: ResMut<EncounterStrength>,
+) {
+    if let Some(system) = unav.get_system(&current_system.0) {
+        encounter_strength.0 = system.properties.ship_strength();
+    }
+}
+
+fn sync_local_factions(
+    unav: Res<UNav>,
+    current_system: Res<CurrentSystem>,
+    mut local_factions: ResMut<LocalFactions>,
+) {
+    local_factions.0 = unav.encounter_odds(&current_system.0);
+}
+
+// each system gets its own backdrop
+fn sync_starfield_seed(
+    unav: Res<UNav>,
+    current_system: Res<CurrentSystem>,
+    mut starfield_seed: ResMut<StarfieldSeed>,
+) {
+    if let Some(system) = unav.get_system(&current_system.0) {
+        starfield_seed.set_if_neq(StarfieldSeed(system.seed));
+    }
+}
+
+// hot systems glow, icy ones are cold and blue
+fn sync_backdrop(
+    unav: Res<UNav>,
+    current_system: Res<CurrentSystem>,
+    mut backdrop: ResMut<SystemBackdrop>,
+) {
+    if let Some(system) = unav.get_system(&current_system.0) {
+        backdrop.set_if_neq(SystemBackdrop {
+            temperature: system.properties.temperature as f32,
+            nebula: system.properties.hazards.nebula as f32,
+            anomaly: system
+                .properties
+                .r#type
+                .contains(&SystemComponents::Anomaly),
+        });
+    }
+}
+
+fn sync_ship_lighting(backdrop: Res<SystemBackdrop>, mut lighting: ResMut<ShipLighting>) {
+    lighting.0 = backdrop.ambient();
+}
+
+// rocks to mine in systems with asteroid belts, as generated: what has been
+// mined since comes off the rocks through the chunk deltas
+fn sync_asteroids(
+    unav: Res<UNav>,
+    current_system: Res<CurrentSystem>,
+    mut asteroids: ResMut<SystemAsteroids>,
+) {
+    if let Some(system) = unav.get_system(&current_system.0) {
+        let generated = SystemContents::generate(system.seed, &system.properties);
+        asteroids.set_if_neq(SystemAsteroids {
+            seed: system.seed,
+            belts: generated.asteroid_belts,
+        });
+    }
+}
+
+// scenes pick up where a loaded save left them
+fn load_chunk_deltas(unav: Res<UNav>, mut deltas: ResMut<ChunkDeltas>) {
+    deltas.set_if_neq(unav.chunks.clone());
+}
+
+// and the save keeps up with what the player mines and loots
+fn save_chunk_deltas(deltas: Res<ChunkDeltas>, mut unav: ResMut<UNav>) {
+    if unav.chunks != *deltas {
+        unav.chunks = deltas.clone();
+    }
+}
+
+// wreckage means a derelict to pick through, otherwise a crewed station
+fn sync_station(
+    unav: Res<UNav>,
+    current_system: Res<CurrentSystem>,
+    mut station: ResMut<SystemStation>,
+) {
+    if let Some(system) = unav.get_system(&current_system.0) {
+        let has = |component| system.properties.r#type.contains(&component);
+        let kind = if has(SystemComponents::Wreckage) {
+            Some(StationKind::Derelict)
+        } else if has(SystemComponents::Station) {
+            Some(StationKind::Intact)
+        } else {
+            None
+        };
+        station.set_if_neq(SystemStation {
+            seed: system.seed,
+            kind,
+            wrecks: system.contents.wrecks.clone(),
+        });
+    }
+}
+
+fn on_goal_event(mut goal_events: EventReader<GoalEvent>) {
+    for event in goal_events.read() {
+        match event {
+            GoalEvent::ObjectiveCompleted { level, .. } => info!("Objective {} completed", level),
+            GoalEvent::Won => info!("Goal reached, you win"),
+            GoalEvent::Lost => info!("Out of jumps, you lose"),
+        }
+    }
+}
+
+fn on_quest_completed(mut completed: EventReader<QuestCompleted>, mut cargo: ResMut<Cargo>) {
+    for QuestCompleted(quest) in completed.read() {
+        info!("Quest done: {}", quest);
+        cargo.add_loot(quest.reward, quest.amount);
+    }
+}
+
+/// How close the ship has to be to a rock's edge to mine it, or to a
+/// container to loot it.
+const MINING_RANGE: f32 = 64.0;
+
+/// How much one press of M takes out of a rock.
+const MINING_AMOUNT: u32 = 10;
+
+// M mines the richest deposit of the closest rock in range
+fn mine_nearest_asteroid(
+    focus: Res<Focus>,
+    input: Res<ButtonInput<KeyCode>>,
+    ship: Query<&GlobalTransform, With<PlayerShip>>,
+    rocks: Query<(Entity, &GlobalTransform, &Asteroid)>,
+    mut mine_events: EventWriter<MineAsteroid>,
+) {
+    if !matches!(*focus, Focus::Game) || !input.just_pressed(KeyCode::KeyM) {
+        return;
+    }
+    let Ok(ship) = ship.get_single() else {
+        return;
+    };
+    let position = ship.translation().truncate();
+    let nearest = rocks
+        .iter()
+        .map(|(entity, transform, asteroid)| {
+            let gap = transform.translation().truncate().distance(position) - asteroid.radius;
+            (entity, gap, asteroid)
+        })
+        .filter(|(_, gap, _)| *gap <= MINING_RANGE)
+        .min_by(|a, b| a.1.total_cmp(&b.1));
+    let Some((entity, _, asteroid)) = nearest else {
+        info!("No rocks in mining range");
+        return;
+    };
+    if let Some(deposit) = asteroid.yields.iter().max_by_key(|y| y.amount) {
+        mine_events.send(MineAsteroid {
+            asteroid: entity,
+            resource: deposit.resource,
+            amount: MINING_AMOUNT,
+        });
+    }
+}
+
+// E empties the closest container in range
+fn loot_nearest_container(
+    focus: Res<Focus>,
+    input: Res<ButtonInput<KeyCode>>,
+    ship: Query<&GlobalTransform, With<PlayerShip>>,
+    containers: Query<(Entity, &GlobalTransform, &Container)>,
+    mut loot_events: EventWriter<LootContainer>,
+) {
+    if !matches!(*focus, Focus::Game) || !input.just_pressed(KeyCode::KeyE) {
+        return;
+    }
+    let Ok(ship) = ship.get_single() else {
+        return;
+    };
+    let position = ship.translation().truncate();
+    let nearest = containers
+        .iter()
+        .filter(|(_, _, container)| !container.is_empty())
+        .map(|(entity, transform, _)| {
+            (
+                entity,
+                transform.translation().truncate().distance(position),
+            )
+        })
+        .filter(|(_, distance)| *distance <= MINING_RANGE)
+        .min_by(|a, b| a.1.total_cmp(&b.1));
+    match nearest {
+        Some((container, _)) => {
+            loot_events.send(LootContainer { container });
+        }
+        None => info!("Nothing to loot in range"),
+    }
+}
+
+// what comes out of a rock comes out of its belt on the map, so it's saved
+fn on_asteroid_mined(
+    mut mined_events: EventReader<AsteroidMined>,
+    current_system: Res<CurrentSystem>,
+    mut unav: ResMut<UNav>,
+) {
+    for mined in mined_events.read() {
+        let belt = unav
+            .systems
+            .get_mut(&current_system.0)
+            .and_then(|system| system.contents.asteroid_belts.get_mut(mined.belt));
+        match belt {
+            Some(belt) => {
+                belt.mine(mined.resource, mined.amount);
+            }
+            None => warn!("Mined a rock of unknown belt {}", mined.belt),
+        }
+    }
+}
+
+// L sends a landing party down, to each planet of the system in turn
+fn land_on_planet(
+    focus: Res<Focus>,
+    input: Res<ButtonInput<KeyCode>>,
+    unav: Res<UNav>,
+    current_system: Res<CurrentSystem>,
+    mut landings: Local<u64>,
+    mut explore_events: EventWriter<ExplorePlanet>,
+) {
+    if !matches!(*focus, Focus::Game) || !input.just_pressed(KeyCode::KeyL) {
+        return;
+    }
+    let Some(system) = unav.get_system(&current_system.0) else {
+        return;
+    };
+    let planets = &system.contents.planets;
+    if planets.is_empty() {
+        info!("No planets to land on here");
+        return;
+    }
+
+    let planet = &planets[*landings as usize % planets.len()];
+    explore_events.send(ExplorePlanet {
+        planet: planet.name.clone(),
+        biome: planet.biome,
+        seed: system.seed ^ *landings,
+    });
+    *landings += 1;
+}
+
+fn on_planet_explored(mut explored_events: EventReader<PlanetExplored>) {
+    for PlanetExplored { planet, resolution } in explored_events.read() {
+        info!(
+            "{} on {}: rolled {} + {}, {}, {:?}",
+            resolution.encounter,
+            planet,
+            resolution.roll,
+            resolution.bonus,
+            if resolution.success {
+                "success"
+            } else {
+                "failure"
+            },
+            resolution.effects
+        );
+    }
+}
+
+// fleets the player runs into show up as ships next to them
+fn on_fleet_encounter(
+    mut encounters: EventReader<FleetEncounter>,
+    encounter_strength: Res<EncounterStrength>,
+    mut spawn_ship_event: EventWriter<SpawnShipEvent>,
+) {
+    for (i, FleetEncounter(fleet)) in encounters.read().enumerate() {
+        info!("Encountered a {:?} fleet", fleet.kind);
+        spawn_ship_event.send(SpawnShipEvent {
+            player: false,
+            position: Vec2::new(200.0, 100.0 * i as f32),
+            seed: fleet.id as u64,
+            strength: encounter_strength.0,
+            faction: Some(fleet.kind.faction()),
+        });
+    }
+}